@@ -0,0 +1,159 @@
+use serde::Deserialize;
+use std::path::Path;
+
+pub const SUPPORTED_CONFIG_TYPES: [&str; 2] = ["toml", "json"];
+
+/// Options read from a --config file.
+///
+/// The keys are the same as the long command line options, with underscores
+/// instead of dashes, e.g. `title_key = "summary"`.
+/// Every option is optional, options given on the command line take precedence.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub separator: Option<String>,
+    pub no_header: Option<bool>,
+    pub detect_delimiter: Option<bool>,
+    pub quote_char: Option<char>,
+    pub escape_char: Option<char>,
+    pub comment_char: Option<char>,
+    pub flexible: Option<bool>,
+    pub lenient: Option<bool>,
+    pub min_columns: Option<usize>,
+    pub skip_rows: Option<usize>,
+    pub case_sensitive_keys: Option<bool>,
+    pub paragraph_mode: Option<bool>,
+    pub preset: Option<String>,
+    pub encoding: Option<String>,
+    pub title_key: Option<String>,
+    pub title_index: Option<usize>,
+    pub description_key: Option<String>,
+    pub description_index: Option<usize>,
+    pub description_keys: Option<String>,
+    pub description_indices: Option<String>,
+    pub description_from_file: Option<bool>,
+    pub upload_images: Option<bool>,
+    pub url: Option<String>,
+    pub token: Option<String>,
+    pub token_file: Option<std::path::PathBuf>,
+    pub job_token: Option<bool>,
+    pub project_name: Option<String>,
+    pub project_id: Option<u64>,
+    pub project_url: Option<String>,
+    pub project_key: Option<String>,
+    pub project_index: Option<usize>,
+    pub skip_unknown_project: Option<bool>,
+    pub group_id: Option<u64>,
+    pub group_name: Option<String>,
+    pub labels: Option<String>,
+    pub label_color: Option<String>,
+    pub label_separator: Option<char>,
+    pub labels_mode: Option<String>,
+    pub labels_key: Option<String>,
+    pub labels_index: Option<usize>,
+    pub due_date_key: Option<String>,
+    pub due_date_index: Option<usize>,
+    pub created_at: Option<String>,
+    pub created_at_key: Option<String>,
+    pub created_at_index: Option<usize>,
+    pub create_labels: Option<bool>,
+    pub assignee: Option<String>,
+    pub assignees: Option<String>,
+    pub assignee_id: Option<u64>,
+    pub assignee_key: Option<String>,
+    pub assignee_index: Option<usize>,
+    pub comment_key: Option<String>,
+    pub comment_index: Option<usize>,
+    pub relates_to_key: Option<String>,
+    pub relates_to_index: Option<usize>,
+    pub blocks_key: Option<String>,
+    pub blocks_index: Option<usize>,
+    pub blocked_by_key: Option<String>,
+    pub blocked_by_index: Option<usize>,
+    pub skip_unknown_assignee: Option<bool>,
+    pub assignee_fallback: Option<String>,
+    pub assignee_fallback_unset: Option<bool>,
+    pub include_inherited_members: Option<bool>,
+    pub milestone: Option<String>,
+    pub create_milestone: Option<bool>,
+    pub milestone_due_date: Option<String>,
+    pub epic_id: Option<u64>,
+    pub parent_iid: Option<u64>,
+    pub confidential: Option<bool>,
+    pub confidential_key: Option<String>,
+    pub weight: Option<u32>,
+    pub weight_key: Option<String>,
+    pub weight_index: Option<usize>,
+    pub estimate_key: Option<String>,
+    pub estimate_index: Option<usize>,
+    pub issue_type: Option<String>,
+    pub issue_type_key: Option<String>,
+    pub issue_type_index: Option<usize>,
+    pub filter_key: Option<String>,
+    pub filter_index: Option<usize>,
+    pub filter_since: Option<String>,
+    pub filter_until: Option<String>,
+    pub strict: Option<bool>,
+    pub sheet: Option<String>,
+    pub sheet_index: Option<usize>,
+    pub json_root: Option<String>,
+    pub prepend_title: Option<String>,
+    pub prepend_description: Option<String>,
+    pub append_description: Option<String>,
+    pub combine_remaining: Option<bool>,
+    pub no_description: Option<bool>,
+    pub skip_empty_fields: Option<bool>,
+    pub combine_format: Option<String>,
+    pub description_template: Option<String>,
+    pub strict_template: Option<bool>,
+    pub allow_undefined_env: Option<bool>,
+    pub skip_existing: Option<bool>,
+    pub validate_roundtrip: Option<bool>,
+    pub idempotent: Option<bool>,
+    pub state_file: Option<std::path::PathBuf>,
+    pub fail_fast: Option<bool>,
+    pub dedup: Option<bool>,
+    pub dedup_by: Option<String>,
+    pub skip_empty_titles: Option<bool>,
+    pub max_retries: Option<u32>,
+    pub respect_rate_limit: Option<bool>,
+    pub timeout: Option<u64>,
+    pub pool_max_idle: Option<usize>,
+    pub keep_alive: Option<u64>,
+    pub no_ssl_verify: Option<bool>,
+    pub ca_cert: Option<std::path::PathBuf>,
+    pub proxy: Option<String>,
+    pub report: Option<std::path::PathBuf>,
+    pub color: Option<String>,
+    pub verbose: Option<bool>,
+    pub quiet: Option<bool>,
+    pub trace: Option<bool>,
+    pub log_format: Option<String>,
+}
+impl ConfigFile {
+    pub fn from_path(path: &Path) -> Result<ConfigFile, String> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) => return Err(format!("Could not read config file: {}", e)),
+        };
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match extension.as_str() {
+            "toml" => match toml::from_str(&contents) {
+                Ok(c) => Ok(c),
+                Err(e) => Err(format!("Could not parse config file: {}", e)),
+            },
+            "json" => match serde_json::from_str(&contents) {
+                Ok(c) => Ok(c),
+                Err(e) => Err(format!("Could not parse config file: {}", e)),
+            },
+            _ => Err(format!(
+                "Config file type is not supported, expected one of {:?}",
+                SUPPORTED_CONFIG_TYPES
+            )),
+        }
+    }
+}
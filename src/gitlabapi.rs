@@ -1,15 +1,207 @@
-use log::{debug, error, info, warn};
-use reqwest;
+use log::{debug, error, trace, warn};
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::fmt;
+use std::path::Path;
+use std::time::Duration;
 use uuid::Uuid;
 
 use crate::issuefile::IssueFromFile;
 
+// How the labels of an issue and the labels for all issues are combined, see merge_labels
+pub const LABELS_MODES: [&str; 4] = ["merge", "replace", "row-only", "global-only"];
+// Maximum page size allowed by the GitLab API
+const ITEMS_PER_PAGE: u64 = 100;
+// Shown instead of the value of headers that hold secrets
+const REDACTED: &str = "***";
+const SECRET_HEADERS: [&str; 3] = ["private-token", "job-token", "authorization"];
+// With --respect-rate-limit, wait for the rate limit to reset when fewer requests are left
+const RATE_LIMIT_LOW: u64 = 5;
+// Longest wait for a rate limit reset, GitLab resets its limits every minute
+const RATE_LIMIT_MAX_WAIT: u64 = 60;
+// Longest wait between retries, a larger Retry-After from GitLab is capped to this
+const RETRY_AFTER_MAX_WAIT: u64 = 60;
+
+#[derive(Debug)]
+pub enum GitLabApiError {
+    /// The request could not be sent or the response could not be read
+    Network(reqwest::Error),
+    /// GitLab did not answer within the timeout
+    Timeout(reqwest::Error),
+    /// GitLab answered with a non-success status code
+    Http { status: u16, body: String },
+    /// The response could not be parsed into what we expected
+    Parse(String),
+    /// The GitLab url is not a valid http(s) url
+    InvalidUrl(String),
+    /// A custom header is not in the "Name: Value" format or is not allowed
+    InvalidHeader(String),
+    /// The CA certificate could not be read or is not a PEM certificate
+    InvalidCertificate(String),
+    /// The proxy url is not valid
+    InvalidProxy(String),
+    /// A file to upload could not be read
+    InvalidFile(String),
+}
+impl GitLabApiError {
+    /// Extract the error message from a GitLab error response body, if there is one.
+    /// GitLab answers with either {"message": ...} or {"error": "..."}
+    fn gitlab_message(&self) -> Option<String> {
+        let body = match self {
+            GitLabApiError::Http { body, .. } => body,
+            _ => return None,
+        };
+        let json: serde_json::Value = serde_json::from_str(body).ok()?;
+        let message = match json.get("message") {
+            Some(m) => m,
+            None => json.get("error")?,
+        };
+        Some(format_gitlab_message(message))
+    }
+    /// What is likely wrong when GitLab can not be reached, for --health-check
+    pub fn hint(&self) -> Option<&'static str> {
+        // The cause, e.g. a DNS or TLS error, is only found in the sources of the reqwest error
+        let causes = |e: &reqwest::Error| {
+            let mut causes = String::new();
+            let mut source = std::error::Error::source(e);
+            while let Some(s) = source {
+                causes.push_str(&s.to_string().to_lowercase());
+                source = s.source();
+            }
+            causes
+        };
+        match self {
+            GitLabApiError::Http { status: 401, .. } => {
+                Some("The token is invalid, expired or revoked")
+            }
+            GitLabApiError::Http { status: 403, .. } => {
+                Some("The token is missing the api or read_api scope")
+            }
+            GitLabApiError::Http { status: 404, .. } => {
+                Some("No GitLab API was found, check --url")
+            }
+            GitLabApiError::Timeout(_) => {
+                Some("GitLab did not answer in time, check --url or raise --timeout")
+            }
+            GitLabApiError::Network(e) => {
+                let causes = causes(e);
+                if causes.contains("dns error") || causes.contains("failed to lookup address") {
+                    Some("The host of --url could not be resolved (DNS)")
+                } else if causes.contains("certificate")
+                    || causes.contains("tls")
+                    || causes.contains("ssl")
+                {
+                    Some("The TLS connection failed, use --ca-cert if GitLab uses a certificate of an internal CA")
+                } else if e.is_connect() {
+                    Some("Could not connect to GitLab, check --url and --proxy")
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+/// Validation errors come as {"title": ["can't be blank"], ...},
+/// which is turned into "title: can't be blank; ..."
+fn format_gitlab_message(message: &serde_json::Value) -> String {
+    match message {
+        serde_json::Value::String(s) => s.to_string(),
+        serde_json::Value::Array(messages) => messages
+            .iter()
+            .map(format_gitlab_message)
+            .collect::<Vec<String>>()
+            .join(", "),
+        serde_json::Value::Object(fields) => fields
+            .iter()
+            .map(|(field, m)| format!("{}: {}", field, format_gitlab_message(m)))
+            .collect::<Vec<String>>()
+            .join("; "),
+        m => m.to_string(),
+    }
+}
+impl fmt::Display for GitLabApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GitLabApiError::Network(e) => write!(f, "Failed to send request: {}", e),
+            GitLabApiError::Timeout(e) => write!(f, "Request timed out: {}", e),
+            GitLabApiError::Http { status, body } => match self.gitlab_message() {
+                Some(message) => write!(f, "Request was not successful ({}): {}", status, message),
+                None => write!(f, "Request was not successful ({}): {}", status, body),
+            },
+            GitLabApiError::Parse(e) => write!(f, "Failed to parse response: {}", e),
+            GitLabApiError::InvalidUrl(e) => write!(f, "Invalid GitLab url: {}", e),
+            GitLabApiError::InvalidHeader(e) => write!(f, "Invalid header: {}", e),
+            GitLabApiError::InvalidCertificate(e) => write!(f, "Invalid CA certificate: {}", e),
+            GitLabApiError::InvalidProxy(e) => write!(f, "Invalid proxy: {}", e),
+            GitLabApiError::InvalidFile(e) => write!(f, "Could not read file: {}", e),
+        }
+    }
+}
+impl From<reqwest::Error> for GitLabApiError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.is_timeout() {
+            true => GitLabApiError::Timeout(e),
+            false => GitLabApiError::Network(e),
+        }
+    }
+}
+
+/// Color for a label that was created without one, derived from its name.
+/// The same name always gets the same color, also between runs
+pub fn label_color(name: &str) -> String {
+    // 32 bit FNV-1a, the hashers of std may change between Rust versions
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    format!("#{:06X}", hash & 0xFFFFFF)
+}
+/// A string field of a json object, None if it is missing or null
+fn string_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    value[key].as_str().map(|s| s.to_string())
+}
+/// Turn the items of a list response into structs.
+/// Items that miss a field we need, e.g. because GitLab sent null, are skipped with a warning
+fn parse_items<T>(
+    items: Vec<serde_json::Value>,
+    kind: &str,
+    from_json: fn(&serde_json::Value) -> Option<T>,
+) -> Vec<T> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let parsed = from_json(item);
+            if parsed.is_none() {
+                warn!("Skipping {} with missing fields: {}", kind, item);
+            }
+            parsed
+        })
+        .collect()
+}
+/// Turn a single object response into a struct, missing fields are an error
+fn parse_item<T>(
+    item: &serde_json::Value,
+    kind: &str,
+    from_json: fn(&serde_json::Value) -> Option<T>,
+) -> Result<T, GitLabApiError> {
+    from_json(item)
+        .ok_or_else(|| GitLabApiError::Parse(format!("{} with missing fields: {}", kind, item)))
+}
+
 pub struct GitLabProjectMember {
     pub id: u64,
     pub username: String,
-    name: String,
+    pub name: String,
+}
+impl GitLabProjectMember {
+    fn from_json(member: &serde_json::Value) -> Option<Self> {
+        let username = string_field(member, "username")?;
+        Some(GitLabProjectMember {
+            id: member["id"].as_u64()?,
+            name: string_field(member, "name").unwrap_or_else(|| username.clone()),
+            username,
+        })
+    }
 }
 impl fmt::Display for GitLabProjectMember {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -20,18 +212,114 @@ pub struct GitLabProjectLabel {
     id: u64,
     pub name: String,
 }
+impl GitLabProjectLabel {
+    fn from_json(label: &serde_json::Value) -> Option<Self> {
+        Some(GitLabProjectLabel {
+            id: label["id"].as_u64()?,
+            name: string_field(label, "name")?,
+        })
+    }
+}
 impl fmt::Display for GitLabProjectLabel {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}: {}", self.id, self.name)
     }
 }
+pub struct GitLabProjectMilestone {
+    pub id: u64,
+    pub title: String,
+}
+impl GitLabProjectMilestone {
+    fn from_json(milestone: &serde_json::Value) -> Option<Self> {
+        Some(GitLabProjectMilestone {
+            id: milestone["id"].as_u64()?,
+            title: string_field(milestone, "title")?,
+        })
+    }
+}
+impl fmt::Display for GitLabProjectMilestone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.id, self.title)
+    }
+}
+pub struct GitLabExistingIssue {
+    pub iid: u64,
+    pub title: String,
+}
+impl GitLabExistingIssue {
+    fn from_json(issue: &serde_json::Value) -> Option<Self> {
+        Some(GitLabExistingIssue {
+            iid: issue["iid"].as_u64()?,
+            title: string_field(issue, "title").unwrap_or_default(),
+        })
+    }
+}
+impl fmt::Display for GitLabExistingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}: {}", self.iid, self.title)
+    }
+}
+
+pub struct GitLabUser {
+    id: u64,
+    pub username: String,
+    name: String,
+}
+impl GitLabUser {
+    fn from_json(user: &serde_json::Value) -> Option<Self> {
+        let username = string_field(user, "username")?;
+        Some(GitLabUser {
+            id: user["id"].as_u64()?,
+            name: string_field(user, "name").unwrap_or_else(|| username.clone()),
+            username,
+        })
+    }
+}
+impl fmt::Display for GitLabUser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.id, self.username, self.name)
+    }
+}
+
+pub struct GitLabGroup {
+    pub id: u64,
+    name: String,
+    pub full_path: String,
+}
+impl GitLabGroup {
+    fn from_json(group: &serde_json::Value) -> Option<Self> {
+        let full_path = string_field(group, "full_path")?;
+        Some(GitLabGroup {
+            id: group["id"].as_u64()?,
+            name: string_field(group, "name").unwrap_or_else(|| full_path.clone()),
+            full_path,
+        })
+    }
+}
+impl fmt::Display for GitLabGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.id, self.name, self.full_path)
+    }
+}
 
 pub struct GitLabProject {
     pub id: u64,
     pub name: String,
     pub path_with_namespace: String,
-    members: Option<Vec<GitLabProjectMember>>,
-    labels: Option<Vec<GitLabProjectLabel>>,
+}
+impl GitLabProject {
+    fn from_json(project: &serde_json::Value) -> Option<Self> {
+        let path_with_namespace = string_field(project, "path_with_namespace")?;
+        Some(GitLabProject {
+            id: project["id"].as_u64()?,
+            // Fall back to the last part of the path, which is usually the same as the name
+            name: string_field(project, "name").unwrap_or_else(|| {
+                let path = path_with_namespace.rsplit('/').next().unwrap_or_default();
+                path.to_string()
+            }),
+            path_with_namespace,
+        })
+    }
 }
 impl fmt::Display for GitLabProject {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -47,22 +335,164 @@ pub struct GitLabApiRequest {
     base_url: String,
     headers: reqwest::header::HeaderMap,
     client: reqwest::blocking::Client,
+    max_retries: u32,
+    trace: bool,
+    respect_rate_limit: bool,
+    // Unix time the rate limit resets at, set when only a few requests are left
+    rate_limit_reset: Cell<Option<u64>>,
 }
 impl GitLabApiRequest {
-    pub fn new(base_url: &str, token: String, no_ssl_verify: bool) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        base_url: &str,
+        token: String,
+        no_ssl_verify: bool,
+        max_retries: u32,
+        trace: bool,
+        extra_headers: &[String],
+        job_token: bool,
+        ca_cert: Option<&Path>,
+        timeout: u64,
+        proxy: Option<&str>,
+        respect_rate_limit: bool,
+        pool_max_idle: Option<usize>,
+        keep_alive: u64,
+    ) -> Result<Self, GitLabApiError> {
+        let base_url = normalize_base_url(base_url)?;
         let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert("PRIVATE-TOKEN", token.parse().unwrap());
-        let client = reqwest::blocking::Client::builder()
+        for header in extra_headers {
+            let (name, value) = parse_header(header)?;
+            headers.insert(name, value);
+        }
+        let mut token: reqwest::header::HeaderValue = token.parse().unwrap();
+        token.set_sensitive(true);
+        // CI job tokens are sent in their own header
+        match job_token {
+            true => headers.insert("JOB-TOKEN", token),
+            false => headers.insert("PRIVATE-TOKEN", token),
+        };
+        // The client is built once and shared by every request, so the connections in its pool
+        // are reused instead of connecting (and doing the TLS handshake) again for every issue.
+        // Idle connections are closed after keep_alive seconds, which should stay below the idle
+        // timeout of GitLab or a proxy in front of it, so a reused connection is still open.
+        // The same interval is used for TCP keep-alive probes on the open connections.
+        let mut builder = reqwest::blocking::Client::builder()
             .danger_accept_invalid_certs(no_ssl_verify)
-            .build()
-            .unwrap();
-        Self {
-            base_url: format!("{}/api/v4", base_url.to_string()),
+            .timeout(Duration::from_secs(timeout))
+            .pool_idle_timeout(Duration::from_secs(keep_alive))
+            .tcp_keepalive(Duration::from_secs(keep_alive));
+        if let Some(max_idle) = pool_max_idle {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        // Trust an internal CA in addition to the system ones
+        if let Some(path) = ca_cert {
+            builder = builder.add_root_certificate(read_certificate(path)?);
+        }
+        // Without an explicit proxy, reqwest uses the HTTP_PROXY, HTTPS_PROXY and NO_PROXY
+        // environment variables
+        if let Some(proxy) = proxy {
+            debug!("Sending requests through proxy {}", proxy);
+            match reqwest::Proxy::all(proxy) {
+                Ok(p) => builder = builder.proxy(p),
+                Err(e) => return Err(GitLabApiError::InvalidProxy(format!("{}: {}", proxy, e))),
+            }
+        }
+        let client = builder.build().unwrap();
+        Ok(Self {
+            base_url: format!("{}/api/v4", base_url),
             headers,
             client,
+            max_retries,
+            trace,
+            respect_rate_limit,
+            rate_limit_reset: Cell::new(None),
+        })
+    }
+    fn send(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response, GitLabApiError> {
+        // Retry on rate limiting (429) and server errors (5xx), respecting the Retry-After
+        // header if GitLab sends one and falling back to exponential backoff otherwise.
+        // A POST that got a server error may still have created the issue, note or link,
+        // so POSTs are only retried on 429 where GitLab rejected the request outright
+        let mut attempt: u32 = 0;
+        loop {
+            // Our requests only have json or byte bodies, so they can always be cloned
+            let request = request.try_clone().unwrap().build()?;
+            let is_post = request.method() == reqwest::Method::POST;
+            if is_post {
+                self.wait_for_rate_limit();
+            }
+            if self.trace {
+                trace_request(&request);
+            }
+            let response = self.client.execute(request)?;
+            let response = match self.trace {
+                true => trace_response(response)?,
+                false => response,
+            };
+            if self.respect_rate_limit {
+                self.update_rate_limit(response.headers());
+            }
+            let status = response.status();
+            let retryable = status.as_u16() == 429 || (status.is_server_error() && !is_post);
+            if !retryable || attempt >= self.max_retries {
+                return Ok(response);
+            }
+            attempt += 1;
+            let wait = response
+                .headers()
+                .get("Retry-After")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.parse::<u64>().ok())
+                .unwrap_or(2u64.pow(attempt - 1))
+                .min(RETRY_AFTER_MAX_WAIT);
+            warn!(
+                "Got {} from GitLab, retrying in {} seconds (attempt {}/{})",
+                status, wait, attempt, self.max_retries
+            );
+            std::thread::sleep(Duration::from_secs(wait));
         }
     }
-    fn get(&self, path: &str) -> Result<reqwest::blocking::Response, &'static str> {
+    /// Remember when the rate limit resets if only a few requests are left,
+    /// from the RateLimit-Remaining and RateLimit-Reset headers GitLab sends
+    fn update_rate_limit(&self, headers: &reqwest::header::HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.parse::<u64>().ok())
+        };
+        if let (Some(remaining), Some(reset)) =
+            (header("RateLimit-Remaining"), header("RateLimit-Reset"))
+        {
+            debug!("Rate limit: {} requests left until {}", remaining, reset);
+            if remaining <= RATE_LIMIT_LOW {
+                self.rate_limit_reset.set(Some(reset));
+            }
+        }
+    }
+    /// Wait until the rate limit resets, if it was about to run out
+    fn wait_for_rate_limit(&self) {
+        let reset = match self.rate_limit_reset.take() {
+            Some(r) => r,
+            None => return,
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let wait = reset.saturating_sub(now).min(RATE_LIMIT_MAX_WAIT);
+        if wait > 0 {
+            warn!(
+                "Almost out of requests allowed by the GitLab rate limit, waiting {} seconds for it to reset",
+                wait
+            );
+            std::thread::sleep(Duration::from_secs(wait));
+        }
+    }
+    fn get(&self, path: &str) -> Result<reqwest::blocking::Response, GitLabApiError> {
         // Create the url, if the path is /projects, the url will be <GITLAB_URL>/api/v4/projects
         // Check if the first character of the path is a /, if it is, remove it
         let path = if path.chars().nth(0).unwrap() == '/' {
@@ -72,23 +502,22 @@ impl GitLabApiRequest {
         };
         let url = format!("{}/{}", self.base_url, path);
         debug!("Sending GET request to {}", url);
-        let response = match self.client.get(&url).headers(self.headers.clone()).send() {
-            Ok(response) => response,
-            Err(_) => return Err("Failed to send request"),
-        };
+        let response = self.send(self.client.get(&url).headers(self.headers.clone()))?;
         debug!("Response rc: {}", &response.status());
         // Check if the response was successful
         if !response.status().is_success() {
-            debug!("Unsuccesful response body: {}", &response.text().unwrap());
-            return Err("Request was not successful");
+            let status = response.status().as_u16();
+            let body = response.text()?;
+            debug!("Unsuccesful response body: {}", &body);
+            return Err(GitLabApiError::Http { status, body });
         }
         Ok(response)
     }
     fn post(
         &self,
         path: &str,
-        body: &HashMap<&str, String>,
-    ) -> Result<reqwest::blocking::Response, &'static str> {
+        body: &HashMap<&str, serde_json::Value>,
+    ) -> Result<reqwest::blocking::Response, GitLabApiError> {
         // Create the url, if the path is /projects, the url will be <GITLAB_URL>/api/v4/projects
         // Check if the first character of the path is a /, if it is, remove it
         let path = if path.chars().nth(0).unwrap() == '/' {
@@ -98,154 +527,477 @@ impl GitLabApiRequest {
         };
         let url = format!("{}/{}", self.base_url, path);
         debug!("Sending POST request to {}", url);
-        let response = match self
-            .client
-            .post(&url)
-            .headers(self.headers.clone())
-            .json(&body)
-            .send()
-        {
-            Ok(response) => response,
-            Err(_) => return Err("Failed to send request"),
-        };
+        let response = self.send(
+            self.client
+                .post(&url)
+                .headers(self.headers.clone())
+                .json(&body),
+        )?;
         debug!("Response rc: {}", &response.status());
         // Check if the response was successful
         if !response.status().is_success() {
-            debug!("Unsuccesful response body: {}", &response.text().unwrap());
-            return Err("Request was not successful");
+            let status = response.status().as_u16();
+            let body = response.text()?;
+            debug!("Unsuccesful response body: {}", &body);
+            return Err(GitLabApiError::Http { status, body });
         }
         Ok(response)
     }
-    pub fn get_projects(&self) -> Result<Vec<GitLabProject>, &'static str> {
-        debug!("Getting projects from GitLab (GET /projects)");
-        let path = "projects";
-        let response = match self.get(path) {
-            Ok(response) => response,
-            Err(_) => return Err("Failed to send request"),
-        };
-        // Check if the response was successful
+    fn delete(&self, path: &str) -> Result<reqwest::blocking::Response, GitLabApiError> {
+        let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+        debug!("Sending DELETE request to {}", url);
+        let response = self.send(self.client.delete(&url).headers(self.headers.clone()))?;
+        debug!("Response rc: {}", &response.status());
         if !response.status().is_success() {
-            return Err("Request was not successful");
+            let status = response.status().as_u16();
+            let body = response.text()?;
+            debug!("Unsuccesful response body: {}", &body);
+            return Err(GitLabApiError::Http { status, body });
         }
-        // Parse the response with serde before turning the important info into a vector of structs
-        let projects_array: Vec<serde_json::Value> = match response.json() {
-            Ok(projects_array) => projects_array,
+        Ok(response)
+    }
+    fn get_all_pages(&self, path: &str) -> Result<Vec<serde_json::Value>, GitLabApiError> {
+        // GitLab paginates list endpoints (20 items per page by default), so keep requesting
+        // pages until the X-Next-Page header is empty
+        let mut items: Vec<serde_json::Value> = Vec::new();
+        let mut page: u64 = 1;
+        loop {
+            let separator = if path.contains('?') { '&' } else { '?' };
+            let page_path = format!(
+                "{}{}per_page={}&page={}",
+                path, separator, ITEMS_PER_PAGE, page
+            );
+            let response = self.get(&page_path)?;
+            // X-Next-Page is an empty string on the last page
+            let next_page = response
+                .headers()
+                .get("X-Next-Page")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.parse::<u64>().ok());
+            // Parse the response with serde before turning the important info into a vector of structs
+            let page_items: Vec<serde_json::Value> = match response.json() {
+                Ok(page_items) => page_items,
+                Err(e) => {
+                    error!("Error parsing page {} of {}: {}", page, path, e);
+                    return Err(GitLabApiError::Parse(e.to_string()));
+                }
+            };
+            debug!(
+                "Got {} items from page {} of {}",
+                page_items.len(),
+                page,
+                path
+            );
+            items.extend(page_items);
+            match next_page {
+                Some(p) => page = p,
+                None => break,
+            }
+        }
+        Ok(items)
+    }
+    /// Get the user the token belongs to.
+    /// This is a cheap request, used to check that the token works before doing anything else
+    pub fn get_current_user(&self) -> Result<GitLabUser, GitLabApiError> {
+        debug!("Getting current user from GitLab (GET /user)");
+        let response = self.get("user")?;
+        let user: serde_json::Value = match response.json() {
+            Ok(user) => user,
             Err(e) => {
-                error!("Error parsing projects: {}", e);
-                return Err("Failed to parse response");
+                error!("Error parsing user {}", e);
+                return Err(GitLabApiError::Parse(e.to_string()));
             }
         };
-        let mut projects: Vec<GitLabProject> = Vec::new();
-        // Turn the response into a vector of structs
-        for project in projects_array {
-            let p = GitLabProject {
-                id: project["id"].as_u64().unwrap(),
-                name: project["name"].as_str().unwrap().to_string(),
-                path_with_namespace: project["path_with_namespace"].as_str().unwrap().to_string(),
-                members: None,
-                labels: None,
-            };
-            projects.push(p);
+        parse_item(&user, "user", GitLabUser::from_json)
+    }
+    /// The version of GitLab, e.g. 16.11.0-ee
+    pub fn get_version(&self) -> Result<String, GitLabApiError> {
+        debug!("Getting version from GitLab (GET /version)");
+        let response = self.get("version")?;
+        let version: serde_json::Value = match response.json() {
+            Ok(version) => version,
+            Err(e) => return Err(GitLabApiError::Parse(e.to_string())),
+        };
+        match string_field(&version, "version") {
+            Some(v) => Ok(v),
+            None => Err(GitLabApiError::Parse(String::from(
+                "The version is missing in the response",
+            ))),
         }
-        Ok(projects)
     }
-    pub fn get_members_of_project(
+    pub fn get_projects(&self) -> Result<Vec<GitLabProject>, GitLabApiError> {
+        debug!("Getting projects from GitLab (GET /projects)");
+        let path = "projects";
+        let projects_array = self.get_all_pages(path)?;
+        // Turn the response into a vector of structs
+        Ok(parse_items(
+            projects_array,
+            "project",
+            GitLabProject::from_json,
+        ))
+    }
+    pub fn get_project_by_path(
         &self,
-        project_id: u64,
-    ) -> Result<Vec<GitLabProjectMember>, &'static str> {
-        let path = format!("projects/{}/members", project_id);
-        let response = match self.get(&path) {
-            Ok(response) => response,
-            Err(_) => return Err("Failed to send request"),
+        path_with_namespace: &str,
+    ) -> Result<GitLabProject, GitLabApiError> {
+        // GitLab accepts the url encoded path of the project instead of the id,
+        // e.g. group/subgroup/project -> group%2Fsubgroup%2Fproject
+        let path = format!("projects/{}", path_with_namespace.replace('/', "%2F"));
+        debug!(
+            "Getting project {} from GitLab (GET /{})",
+            path_with_namespace, path
+        );
+        let response = self.get(&path)?;
+        let project: serde_json::Value = match response.json() {
+            Ok(project) => project,
+            Err(e) => {
+                error!("Error parsing project {}", e);
+                return Err(GitLabApiError::Parse(e.to_string()));
+            }
         };
-        // Check if the response was successful
-        if !response.status().is_success() {
-            return Err("Request was not successful");
-        }
-        // Parse the response with serde before turning the important info into a vector of structs
-        let members_array: Vec<serde_json::Value> = match response.json() {
-            Ok(members) => members,
+        parse_item(&project, "project", GitLabProject::from_json)
+    }
+    pub fn get_group(&self, id_or_path: &str) -> Result<GitLabGroup, GitLabApiError> {
+        // Like projects, groups can be looked up by id or by their url encoded full path
+        let path = format!("groups/{}", id_or_path.replace('/', "%2F"));
+        debug!("Getting group {} from GitLab (GET /{})", id_or_path, path);
+        let response = self.get(&path)?;
+        let group: serde_json::Value = match response.json() {
+            Ok(group) => group,
             Err(e) => {
-                error!("Error parsing members {}", e);
-                return Err("Failed to parse response");
+                error!("Error parsing group {}", e);
+                return Err(GitLabApiError::Parse(e.to_string()));
             }
         };
-        let mut members: Vec<GitLabProjectMember> = Vec::new();
-        for member in members_array {
-            let m = GitLabProjectMember {
-                id: member["id"].as_u64().unwrap(),
-                username: member["username"].as_str().unwrap().to_string(),
-                name: member["name"].as_str().unwrap().to_string(),
-            };
-            members.push(m);
-        }
-        Ok(members)
+        parse_item(&group, "group", GitLabGroup::from_json)
+    }
+    pub fn get_projects_of_group(
+        &self,
+        group_id: u64,
+    ) -> Result<Vec<GitLabProject>, GitLabApiError> {
+        debug!("Getting projects of group {} from GitLab", group_id);
+        let path = format!("groups/{}/projects?include_subgroups=true", group_id);
+        let projects_array = self.get_all_pages(&path)?;
+        Ok(parse_items(
+            projects_array,
+            "project",
+            GitLabProject::from_json,
+        ))
+    }
+    /// Get the members of a project. With include_inherited, the members of the
+    /// parent groups are included too, as they can be assigned to issues as well
+    pub fn get_members_of_project(
+        &self,
+        project_id: u64,
+        include_inherited: bool,
+    ) -> Result<Vec<GitLabProjectMember>, GitLabApiError> {
+        let path = match include_inherited {
+            true => format!("projects/{}/members/all", project_id),
+            false => format!("projects/{}/members", project_id),
+        };
+        let members_array = self.get_all_pages(&path)?;
+        Ok(parse_items(
+            members_array,
+            "member",
+            GitLabProjectMember::from_json,
+        ))
     }
 
     pub fn get_labels_of_project(
         &self,
         project_id: u64,
-    ) -> Result<Vec<GitLabProjectLabel>, &'static str> {
+    ) -> Result<Vec<GitLabProjectLabel>, GitLabApiError> {
         let path = format!("projects/{}/labels", project_id);
-        let response = match self.get(&path) {
-            Ok(response) => response,
-            Err(_) => return Err("Failed to send request"),
+        let labels_array = self.get_all_pages(&path)?;
+        Ok(parse_items(
+            labels_array,
+            "label",
+            GitLabProjectLabel::from_json,
+        ))
+    }
+
+    /// Get the members and the labels of a project in one go, for verifying the
+    /// assignees and labels of the issues. Only the lists that are wanted are requested.
+    pub fn get_members_and_labels_of_project(
+        &self,
+        project_id: u64,
+        members: bool,
+        include_inherited: bool,
+        labels: bool,
+    ) -> Result<(Vec<GitLabProjectMember>, Vec<GitLabProjectLabel>), GitLabApiError> {
+        let members = match members {
+            true => self.get_members_of_project(project_id, include_inherited)?,
+            false => Vec::new(),
         };
-        // Check if the response was successful
-        if !response.status().is_success() {
-            return Err("Request was not successful");
-        }
-        // Parse the response with serde before turning the important info into a vector of structs
-        let labels_array: Vec<serde_json::Value> = match response.json() {
-            Ok(labels) => labels,
+        let labels = match labels {
+            true => self.get_labels_of_project(project_id)?,
+            false => Vec::new(),
+        };
+        Ok((members, labels))
+    }
+
+    pub fn get_milestones_of_project(
+        &self,
+        project_id: u64,
+    ) -> Result<Vec<GitLabProjectMilestone>, GitLabApiError> {
+        let path = format!("projects/{}/milestones", project_id);
+        let milestones_array = self.get_all_pages(&path)?;
+        Ok(parse_items(
+            milestones_array,
+            "milestone",
+            GitLabProjectMilestone::from_json,
+        ))
+    }
+
+    pub fn get_issues_of_project(
+        &self,
+        project_id: u64,
+    ) -> Result<Vec<GitLabExistingIssue>, GitLabApiError> {
+        let path = format!("projects/{}/issues?state=opened", project_id);
+        let issues_array = self.get_all_pages(&path)?;
+        Ok(parse_items(
+            issues_array,
+            "issue",
+            GitLabExistingIssue::from_json,
+        ))
+    }
+
+    /// Find an issue of the project with the import id marker in its description
+    pub fn find_issue_by_import_id(
+        &self,
+        project_id: u64,
+        import_id: &Uuid,
+    ) -> Result<Option<GitLabExistingIssue>, GitLabApiError> {
+        let path = format!(
+            "projects/{}/issues?in=description&search={}",
+            project_id, import_id
+        );
+        let marker = import_marker(import_id);
+        let issues_array = self.get_all_pages(&path)?;
+        // The search is fuzzy, so make sure the marker is really there
+        Ok(issues_array
+            .iter()
+            .find(|issue| {
+                issue["description"]
+                    .as_str()
+                    .is_some_and(|d| d.contains(&marker))
+            })
+            .and_then(GitLabExistingIssue::from_json))
+    }
+
+    pub fn create_label(
+        &self,
+        project_id: u64,
+        name: &str,
+        color: Option<&str>,
+    ) -> Result<GitLabProjectLabel, GitLabApiError> {
+        let path = format!("projects/{}/labels", project_id);
+        let mut body = HashMap::new();
+        body.insert("name", name.into());
+        let color = match color {
+            Some(c) => c.to_string(),
+            None => label_color(name),
+        };
+        body.insert("color", color.into());
+        let response = self.post(&path, &body)?;
+        let label: serde_json::Value = match response.json() {
+            Ok(label) => label,
             Err(e) => {
-                error!("Error parsing labels {}", e);
-                return Err("Failed to parse response");
+                error!("Error parsing created label {}", e);
+                return Err(GitLabApiError::Parse(e.to_string()));
             }
         };
-        let mut labels: Vec<GitLabProjectLabel> = Vec::new();
-        for label in labels_array {
-            let l = GitLabProjectLabel {
-                id: label["id"].as_u64().unwrap(),
-                name: label["name"].as_str().unwrap().to_string(),
-            };
-            labels.push(l);
+        parse_item(&label, "label", GitLabProjectLabel::from_json)
+    }
+    /// Create a milestone in the project and return its id
+    pub fn create_milestone(
+        &self,
+        project_id: u64,
+        title: &str,
+        due_date: Option<&str>,
+    ) -> Result<u64, GitLabApiError> {
+        let path = format!("projects/{}/milestones", project_id);
+        let mut body = HashMap::new();
+        body.insert("title", title.into());
+        if let Some(due_date) = due_date {
+            body.insert("due_date", due_date.into());
         }
-        Ok(labels)
+        let response = self.post(&path, &body)?;
+        let milestone: serde_json::Value = match response.json() {
+            Ok(milestone) => milestone,
+            Err(e) => {
+                error!("Error parsing created milestone {}", e);
+                return Err(GitLabApiError::Parse(e.to_string()));
+            }
+        };
+        parse_item(&milestone, "milestone", GitLabProjectMilestone::from_json).map(|m| m.id)
+    }
+    /// Add an issue to an epic of a group. Epics need GitLab Premium,
+    /// other instances answer with 403 or 404.
+    /// The issue is referenced by its global id, not by its iid
+    pub fn add_issue_to_epic(
+        &self,
+        group_id: u64,
+        epic_iid: u64,
+        issue_id: u64,
+    ) -> Result<(), GitLabApiError> {
+        let path = format!("groups/{}/epics/{}/issues/{}", group_id, epic_iid, issue_id);
+        debug!(
+            "Adding issue {} to epic {} of group {}",
+            issue_id, epic_iid, group_id
+        );
+        self.post(&path, &HashMap::new())?;
+        Ok(())
     }
 
-    pub fn get_projects_with_members_and_labels(&self) -> Result<Vec<GitLabProject>, &'static str> {
-        let mut projects = match self.get_projects() {
-            Ok(projects) => projects,
-            Err(_) => return Err("Failed to get projects"),
+    /// Link an issue to another issue, possibly in another project. Both issues are
+    /// referenced by their iid, link_type is relates_to, blocks or is_blocked_by.
+    /// blocks and is_blocked_by need GitLab Premium
+    pub fn link_issues(
+        &self,
+        project_id: u64,
+        issue_iid: u64,
+        target_project_id: u64,
+        target_issue_iid: u64,
+        link_type: &str,
+    ) -> Result<(), GitLabApiError> {
+        let path = format!("projects/{}/issues/{}/links", project_id, issue_iid);
+        debug!(
+            "Linking issue {} of project {} to issue {} of project {} ({})",
+            issue_iid, project_id, target_issue_iid, target_project_id, link_type
+        );
+        let mut body = HashMap::new();
+        body.insert("target_project_id", target_project_id.into());
+        body.insert("target_issue_iid", target_issue_iid.into());
+        body.insert("link_type", link_type.into());
+        self.post(&path, &body)?;
+        Ok(())
+    }
+
+    /// Add a comment to an issue
+    pub fn add_note(
+        &self,
+        project_id: u64,
+        issue_iid: u64,
+        note: &str,
+    ) -> Result<(), GitLabApiError> {
+        let path = format!("projects/{}/issues/{}/notes", project_id, issue_iid);
+        debug!(
+            "Adding a comment to issue {} of project {}",
+            issue_iid, project_id
+        );
+        let mut body = HashMap::new();
+        body.insert("body", note.into());
+        self.post(&path, &body)?;
+        Ok(())
+    }
+
+    /// Delete an issue, only used by --validate-roundtrip.
+    /// Needs the owner role in the project, or an admin token
+    pub fn delete_issue(&self, project_id: u64, issue_iid: u64) -> Result<(), GitLabApiError> {
+        let path = format!("projects/{}/issues/{}", project_id, issue_iid);
+        debug!("Deleting issue {} of project {}", issue_iid, project_id);
+        self.delete(&path)?;
+        Ok(())
+    }
+
+    /// Upload a file to a project, e.g. an image for the description of an issue.
+    /// Returns the markdown GitLab suggests for linking the file, e.g. ![name](/uploads/...)
+    pub fn upload_file(&self, project_id: u64, file: &Path) -> Result<String, GitLabApiError> {
+        let contents = match std::fs::read(file) {
+            Ok(c) => c,
+            Err(e) => {
+                return Err(GitLabApiError::InvalidFile(format!(
+                    "{}: {}",
+                    file.display(),
+                    e
+                )))
+            }
         };
-        for project in &mut projects {
-            let members = match self.get_members_of_project(project.id) {
-                Ok(members) => members,
-                Err(_) => return Err("Failed to get members of project"),
-            };
-            let labels = match self.get_labels_of_project(project.id) {
-                Ok(labels) => labels,
-                Err(_) => return Err("Failed to get labels of project"),
-            };
-            project.members = Some(members);
-            project.labels = Some(labels);
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().replace('"', ""))
+            .unwrap_or_default();
+        // The file is sent as multipart form data, built by hand so the body stays plain bytes
+        // that can be cloned for retries
+        let boundary = format!("gitlab-issues-from-file-{}", Uuid::new_v4().simple());
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            boundary, file_name
+        )
+        .into_bytes();
+        body.extend(contents);
+        body.extend(format!("\r\n--{}--\r\n", boundary).into_bytes());
+        let url = format!("{}/projects/{}/uploads", self.base_url, project_id);
+        debug!("Uploading {} to {}", file.display(), url);
+        let response = self.send(
+            self.client
+                .post(&url)
+                .headers(self.headers.clone())
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={}", boundary),
+                )
+                .body(body),
+        )?;
+        debug!("Response rc: {}", &response.status());
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text()?;
+            debug!("Unsuccesful response body: {}", &body);
+            return Err(GitLabApiError::Http { status, body });
+        }
+        let uploaded: serde_json::Value = match response.json() {
+            Ok(uploaded) => uploaded,
+            Err(e) => {
+                error!("Error parsing uploaded file {}", e);
+                return Err(GitLabApiError::Parse(e.to_string()));
+            }
+        };
+        match string_field(&uploaded, "markdown") {
+            Some(markdown) => Ok(markdown),
+            None => Err(GitLabApiError::Parse(format!(
+                "upload without markdown: {}",
+                uploaded
+            ))),
         }
-        Ok(projects)
     }
 
-    pub fn post_issue(&self, issue: &GitLabProjectIssue) -> Result<(), &'static str> {
-        let body = issue.create_issue_body();
+    pub fn post_issue(&self, issue: &GitLabProjectIssue) -> Result<PostedIssue, GitLabApiError> {
+        let body = match issue.create_issue_body() {
+            Ok(body) => body,
+            Err(e) => return Err(GitLabApiError::Parse(e.to_string())),
+        };
         let path = format!("projects/{}/issues", issue.project_id);
-        let response = match self.post(&path, &body.unwrap()) {
-            Ok(response) => response,
-            Err(_) => return Err("Failed to send request"),
+        let response = self.post(&path, &body)?;
+        // GitLab answers with the created issue
+        let created: serde_json::Value = match response.json() {
+            Ok(created) => created,
+            Err(e) => {
+                error!("Error parsing created issue {}", e);
+                return Err(GitLabApiError::Parse(e.to_string()));
+            }
         };
-        // Check if the response was successful
-        if !response.status().is_success() {
-            return Err("Request was not successful");
-        }
-        Ok(())
+        Ok(PostedIssue {
+            id: created["id"].as_u64().unwrap_or_default(),
+            iid: created["iid"].as_u64().unwrap_or_default(),
+            title: issue.title.clone(),
+            web_url: created["web_url"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// An issue that was created in GitLab
+#[derive(Debug)]
+pub struct PostedIssue {
+    pub id: u64,
+    pub iid: u64,
+    pub title: String,
+    pub web_url: String,
+}
+impl fmt::Display for PostedIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}: {} ({})", self.iid, self.title, self.web_url)
     }
 }
 
@@ -256,37 +1008,355 @@ pub struct GitLabProjectIssue {
     pub title: String,
     description: Option<String>,
     labels: Option<String>,
-    assignee_id: Option<u64>,
+    assignee_ids: Vec<u64>,
+    due_date: Option<String>,
+    milestone_id: Option<u64>,
+    confidential: bool,
+    weight: Option<u32>,
+    issue_type: Option<String>,
+    created_at: Option<String>,
 }
 impl GitLabProjectIssue {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         project_id: u64,
         issue: &IssueFromFile,
         labels: &Option<String>,
-        assignee_id: Option<u64>,
+        assignee_ids: &[u64],
+        milestone_id: Option<u64>,
+        confidential: bool,
+        weight: Option<u32>,
+        issue_type: &Option<String>,
+        created_at: &Option<String>,
+        labels_mode: &str,
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
             project_id,
             title: issue.title.clone(),
-            description: issue.description.clone(),
-            labels: labels.clone(),
-            assignee_id: assignee_id,
+            description: Self::description_with_estimate(issue),
+            labels: merge_labels(&issue.labels, labels, labels_mode),
+            assignee_ids: assignee_ids.to_vec(),
+            due_date: issue.due_date.clone(),
+            milestone_id,
+            confidential: confidential || issue.confidential,
+            weight: issue.weight.or(weight),
+            issue_type: issue.issue_type.clone().or(issue_type.clone()),
+            created_at: issue.created_at.clone().or(created_at.clone()),
+        }
+    }
+    /// Add the time estimate of the issue as an /estimate quick action at the end of the
+    /// description, GitLab runs quick actions in the description when the issue is created
+    fn description_with_estimate(issue: &IssueFromFile) -> Option<String> {
+        match (&issue.description, &issue.estimate) {
+            (Some(description), Some(estimate)) if !description.trim().is_empty() => Some(format!(
+                "{}\n\n/estimate {}",
+                description.trim_end(),
+                estimate
+            )),
+            (_, Some(estimate)) => Some(format!("/estimate {}", estimate)),
+            (description, None) => description.clone(),
         }
     }
-    fn create_issue_body(&self) -> Result<HashMap<&str, String>, &'static str> {
+    /// Use the import id of an earlier run and add it to the description as a marker,
+    /// so the issue can be found again with find_issue_by_import_id
+    pub fn set_import_id(&mut self, import_id: Uuid) {
+        self.id = import_id;
+        let marker = import_marker(&import_id);
+        self.description = Some(match self.description.take() {
+            Some(description) => format!("{}\n\n{}", description.trim_end(), marker),
+            None => marker,
+        });
+    }
+    pub fn is_backdated(&self) -> bool {
+        self.created_at.is_some()
+    }
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    pub fn set_description(&mut self, description: String) {
+        self.description = Some(description);
+    }
+    pub fn create_issue_body(&self) -> Result<HashMap<&str, serde_json::Value>, &'static str> {
         let mut body = HashMap::new();
-        body.insert("id", self.id.to_string());
-        body.insert("title", self.title.clone());
+        body.insert("id", self.id.to_string().into());
+        body.insert("title", self.title.clone().into());
         if let Some(description) = &self.description {
-            body.insert("description", description.clone());
+            body.insert("description", description.clone().into());
         }
         if let Some(labels) = &self.labels {
-            body.insert("labels", labels.clone());
+            body.insert("labels", labels.clone().into());
+        }
+        // assignee_ids[] in form encoding, a plain array in a json body
+        if !self.assignee_ids.is_empty() {
+            body.insert("assignee_ids", self.assignee_ids.clone().into());
+        }
+        if let Some(due_date) = &self.due_date {
+            body.insert("due_date", due_date.clone().into());
+        }
+        if let Some(milestone_id) = &self.milestone_id {
+            body.insert("milestone_id", (*milestone_id).into());
+        }
+        if self.confidential {
+            body.insert("confidential", true.into());
         }
-        if let Some(assignee_id) = &self.assignee_id {
-            body.insert("assignee_id", assignee_id.to_string());
+        if let Some(weight) = &self.weight {
+            body.insert("weight", (*weight).into());
+        }
+        if let Some(issue_type) = &self.issue_type {
+            body.insert("issue_type", issue_type.clone().into());
+        }
+        // Only allowed for project owners and admins
+        if let Some(created_at) = &self.created_at {
+            body.insert("created_at", created_at.clone().into());
         }
         Ok(body)
     }
 }
+
+/// Hidden in the description of issues created with --idempotent
+fn import_marker(import_id: &Uuid) -> String {
+    format!("<!-- import-id: {} -->", import_id)
+}
+
+/// Read a PEM encoded CA certificate from a file
+fn read_certificate(path: &Path) -> Result<reqwest::Certificate, GitLabApiError> {
+    let pem = match std::fs::read(path) {
+        Ok(pem) => pem,
+        Err(e) => {
+            return Err(GitLabApiError::InvalidCertificate(format!(
+                "Could not read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    match reqwest::Certificate::from_pem(&pem) {
+        Ok(cert) => Ok(cert),
+        Err(e) => Err(GitLabApiError::InvalidCertificate(format!(
+            "{} is not a PEM certificate: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Parse a custom header given as "Name: Value".
+/// The value is marked as sensitive, as custom headers usually hold credentials for a proxy.
+fn parse_header(
+    header: &str,
+) -> Result<(reqwest::header::HeaderName, reqwest::header::HeaderValue), GitLabApiError> {
+    let (name, value) = match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+        _ => {
+            return Err(GitLabApiError::InvalidHeader(format!(
+                "'{}', expected the format 'Name: Value'",
+                header
+            )))
+        }
+    };
+    let name: reqwest::header::HeaderName = match name.parse() {
+        Ok(n) => n,
+        Err(e) => {
+            return Err(GitLabApiError::InvalidHeader(format!(
+                "'{}': {}",
+                header, e
+            )))
+        }
+    };
+    // The token headers are set by us
+    if name == "private-token" || name == "job-token" {
+        return Err(GitLabApiError::InvalidHeader(format!(
+            "'{}' can not be overridden, use --token instead",
+            name
+        )));
+    }
+    let mut value: reqwest::header::HeaderValue = match value.parse() {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(GitLabApiError::InvalidHeader(format!(
+                "'{}': {}",
+                header, e
+            )))
+        }
+    };
+    value.set_sensitive(true);
+    Ok((name, value))
+}
+
+/// Header names and values, with the values of secret headers replaced by REDACTED
+fn redact_headers(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.is_sensitive() || SECRET_HEADERS.contains(&name.as_str()) {
+                true => REDACTED.to_string(),
+                false => value.to_str().unwrap_or_default().to_string(),
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+/// Log everything about a request for --trace
+fn trace_request(request: &reqwest::blocking::Request) {
+    trace!("Request: {} {}", request.method(), request.url());
+    trace!("Request headers: {:?}", redact_headers(request.headers()));
+    if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+        trace!("Request body: {}", String::from_utf8_lossy(body));
+    }
+}
+
+/// Log everything about a response for --trace.
+/// Reading the body consumes the response, so a new one is built from what was read.
+fn trace_response(
+    response: reqwest::blocking::Response,
+) -> Result<reqwest::blocking::Response, GitLabApiError> {
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes()?;
+    trace!("Response: {}", status);
+    trace!("Response headers: {:?}", redact_headers(&headers));
+    trace!("Response body: {}", String::from_utf8_lossy(&body));
+    let mut rebuilt = http::Response::builder().status(status);
+    if let Some(h) = rebuilt.headers_mut() {
+        *h = headers;
+    }
+    match rebuilt.body(body.to_vec()) {
+        Ok(r) => Ok(r.into()),
+        Err(e) => Err(GitLabApiError::Parse(e.to_string())),
+    }
+}
+
+/// Trim trailing slashes from the GitLab url and check that it is a valid http(s) url.
+/// The url should point to the GitLab instance, /api/v4 is appended by us.
+fn normalize_base_url(base_url: &str) -> Result<String, GitLabApiError> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    let url = match reqwest::Url::parse(base_url) {
+        Ok(url) => url,
+        Err(e) => return Err(GitLabApiError::InvalidUrl(format!("{}: {}", base_url, e))),
+    };
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(GitLabApiError::InvalidUrl(format!(
+            "{}: expected an http or https url",
+            base_url
+        )));
+    }
+    if url.path().contains("/api/v4") {
+        return Err(GitLabApiError::InvalidUrl(format!(
+            "{}: the url should not contain /api/v4, it is added automatically",
+            base_url
+        )));
+    }
+    Ok(base_url.to_string())
+}
+
+/// Merge the labels of an issue with the labels given for all issues, in one of LABELS_MODES:
+/// merge uses both, replace the labels of the issue if it has any and the labels for all issues
+/// otherwise, row-only and global-only just one of them.
+/// Both are comma separated lists, duplicates are removed while keeping the order.
+fn merge_labels(
+    issue_labels: &Option<String>,
+    labels: &Option<String>,
+    mode: &str,
+) -> Option<String> {
+    let sources = match mode {
+        "replace" if issue_labels.is_some() => [issue_labels, &None],
+        "replace" => [&None, labels],
+        "row-only" => [issue_labels, &None],
+        "global-only" => [&None, labels],
+        _ => [issue_labels, labels],
+    };
+    let mut merged: Vec<&str> = Vec::new();
+    for label in sources.into_iter().flatten().flat_map(|l| l.split(',')) {
+        let label = label.trim();
+        if !label.is_empty() && !merged.contains(&label) {
+            merged.push(label);
+        }
+    }
+    match merged.is_empty() {
+        true => None,
+        false => Some(merged.join(",")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    // Serve each page once, with X-Next-Page pointing at the next one, and return the
+    // request lines that were received
+    fn serve_pages(
+        pages: Vec<(&'static str, &'static str)>,
+    ) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (body, next_page) in pages {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                requests.push(line.trim().to_string());
+                // Skip the headers, GET requests have no body
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nX-Next-Page: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    next_page,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn get_all_pages_follows_next_page() {
+        let (url, server) =
+            serve_pages(vec![(r#"[{"id":1},{"id":2}]"#, "2"), (r#"[{"id":3}]"#, "")]);
+        let client = GitLabApiRequest::new(
+            &url,
+            "token".to_string(),
+            false,
+            0,
+            false,
+            &[],
+            false,
+            None,
+            5,
+            None,
+            false,
+            None,
+            5,
+        )
+        .unwrap();
+        let items = client.get_all_pages("projects").unwrap();
+        let ids: Vec<u64> = items
+            .iter()
+            .map(|item| item["id"].as_u64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        // The empty X-Next-Page of the second page ends the loop, the server only serves two pages
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests,
+            vec![
+                "GET /api/v4/projects?per_page=100&page=1 HTTP/1.1",
+                "GET /api/v4/projects?per_page=100&page=2 HTTP/1.1",
+            ]
+        );
+    }
+}
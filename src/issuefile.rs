@@ -1,23 +1,60 @@
-use csv::ReaderBuilder;
+use calamine::{DataType, Reader};
+use csv::{ReaderBuilder, StringRecord};
 use log::{debug, error, info, warn};
 use std::fmt;
+use std::io::Read;
 use std::path::PathBuf;
 pub struct IssueFromFile {
     pub title: String,
     pub description: Option<String>,
+    pub labels: Option<String>,
+    pub due_date: Option<String>,
+    pub confidential: bool,
+    pub weight: Option<u32>,
+    pub estimate: Option<String>,
+    pub issue_type: Option<String>,
+    pub assignee: Option<String>,
+    pub created_at: Option<String>,
+    /// Name, path or id of the project to create the issue in, instead of --project-name or --project-id
+    pub project: Option<String>,
+    /// Added as the first comment of the issue after it is created
+    pub comment: Option<String>,
+    /// Titles of other issues of the file to link to, with the GitLab link type,
+    /// e.g. ("blocks", "Set up CI")
+    pub links: Vec<(&'static str, String)>,
 }
 impl fmt::Display for IssueFromFile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Title: '{}', Description: '{}'",
+            "Title: '{}', Description: '{}', Labels: '{}'",
             self.title,
-            self.description.as_ref().unwrap_or(&"".to_string())
+            self.description.as_ref().unwrap_or(&"".to_string()),
+            self.labels.as_ref().unwrap_or(&"".to_string())
         )
     }
 }
 
-pub const SUPPORTED_FILE_TYPES: [&str; 2] = ["csv", "json"];
+pub const SUPPORTED_FILE_TYPES: [&str; 11] = [
+    "csv", "tsv", "json", "jsonl", "ndjson", "yaml", "yml", "xlsx", "org", "md", "txt",
+];
+// Issue types supported by GitLab
+pub const ISSUE_TYPES: [&str; 4] = ["issue", "incident", "test_case", "task"];
+// Delimiters considered by --detect-delimiter
+const DELIMITER_CANDIDATES: [char; 4] = [',', '\t', ';', '|'];
+// Formats of the fields combined into the description, see combine_fields
+pub const COMBINE_FORMATS: [&str; 3] = ["kv", "bullets", "table"];
+// Keywords of the org-mode headlines that are turned into issues
+const ORG_TODO_KEYWORDS: [&str; 2] = ["TODO", "NEXT"];
+// Markers of the unchecked and checked tasks of a markdown checklist
+const MARKDOWN_TASKS: [&str; 2] = ["- [ ]", "* [ ]"];
+const MARKDOWN_DONE_TASKS: [&str; 4] = ["- [x]", "* [x]", "- [X]", "* [X]"];
+// Keys of the front-matter of a markdown task
+const MARKDOWN_FRONT_MATTER_KEYS: [&str; 4] = ["labels", "weight", "estimate", "assignee"];
+// Separator of the titles in the link columns, titles often contain commas
+const LINK_SEPARATOR: char = ';';
+// File name used to read the issues from stdin instead of a file
+pub const STDIN_FILE: &str = "-";
 #[derive(Debug)]
 pub struct FileParser {
     file: PathBuf,
@@ -30,110 +67,724 @@ pub struct FileParser {
     description_column_index: Option<usize>,
     prepend_title: Option<String>,
     combine_remaining: bool,
+    no_description: bool,
+    labels_key: Option<String>,
+    labels_column_index: Option<usize>,
+    due_date_key: Option<String>,
+    due_date_column_index: Option<usize>,
+    confidential_key: Option<String>,
+    confidential_column_index: Option<usize>,
+    weight_key: Option<String>,
+    weight_column_index: Option<usize>,
+    sheet: Option<String>,
+    sheet_index: Option<usize>,
+    skip_empty_titles: bool,
+    estimate_key: Option<String>,
+    estimate_column_index: Option<usize>,
+    detect_delimiter: bool,
+    description_from_file: bool,
+    issue_type_key: Option<String>,
+    issue_type_column_index: Option<usize>,
+    lenient: bool,
+    quote_char: char,
+    prepend_description: Option<String>,
+    append_description: Option<String>,
+    description_template: Option<String>,
+    strict_template: bool,
+    label_separator: char,
+    filter_key: Option<String>,
+    filter_column_index: Option<usize>,
+    filter_since: Option<chrono::NaiveDate>,
+    filter_until: Option<chrono::NaiveDate>,
+    strict: bool,
+    description_keys: Option<Vec<String>>,
+    description_column_indices: Option<Vec<usize>>,
+    assignee_key: Option<String>,
+    assignee_column_index: Option<usize>,
+    min_columns: Option<usize>,
+    case_sensitive_keys: bool,
+    created_at_key: Option<String>,
+    created_at_column_index: Option<usize>,
+    paragraph_mode: bool,
+    project_key: Option<String>,
+    project_column_index: Option<usize>,
+    skip_empty_fields: bool,
+    combine_format: String,
+    json_root: Option<String>,
+    comment_key: Option<String>,
+    comment_column_index: Option<usize>,
+    encoding: Option<&'static encoding_rs::Encoding>,
+    relates_to_key: Option<String>,
+    relates_to_column_index: Option<usize>,
+    blocks_key: Option<String>,
+    blocks_column_index: Option<usize>,
+    blocked_by_key: Option<String>,
+    blocked_by_column_index: Option<usize>,
+    skip_rows: usize,
+    escape_char: Option<char>,
+    comment_char: Option<char>,
+    flexible: bool,
+}
+/// Options of a FileParser, mostly the command line options of the same name.
+/// Unlike on the command line the defaults are empty, so quote_char, label_separator
+/// and combine_format must be set.
+#[derive(Debug, Default)]
+pub struct ParserOptions {
+    pub file: PathBuf,
+    pub separator: Option<char>,
+    pub no_header: bool,
+    pub title_key: Option<String>,
+    pub title_column_index: Option<usize>,
+    pub description_key: Option<String>,
+    pub description_column_index: Option<usize>,
+    pub prepend_title: Option<String>,
+    pub combine_remaining: bool,
+    pub no_description: bool,
+    pub labels_key: Option<String>,
+    pub labels_column_index: Option<usize>,
+    pub due_date_key: Option<String>,
+    pub due_date_column_index: Option<usize>,
+    pub confidential_key: Option<String>,
+    pub format: Option<String>,
+    pub weight_key: Option<String>,
+    pub weight_column_index: Option<usize>,
+    pub sheet: Option<String>,
+    pub sheet_index: Option<usize>,
+    pub skip_empty_titles: bool,
+    pub estimate_key: Option<String>,
+    pub estimate_column_index: Option<usize>,
+    pub detect_delimiter: bool,
+    pub description_from_file: bool,
+    pub issue_type_key: Option<String>,
+    pub issue_type_column_index: Option<usize>,
+    pub lenient: bool,
+    pub quote_char: char,
+    pub prepend_description: Option<String>,
+    pub append_description: Option<String>,
+    pub description_template: Option<String>,
+    pub strict_template: bool,
+    pub label_separator: char,
+    pub filter_key: Option<String>,
+    pub filter_column_index: Option<usize>,
+    pub filter_since: Option<chrono::NaiveDate>,
+    pub filter_until: Option<chrono::NaiveDate>,
+    pub strict: bool,
+    pub description_keys: Option<Vec<String>>,
+    pub description_column_indices: Option<Vec<usize>>,
+    pub assignee_key: Option<String>,
+    pub assignee_column_index: Option<usize>,
+    pub min_columns: Option<usize>,
+    pub case_sensitive_keys: bool,
+    pub created_at_key: Option<String>,
+    pub created_at_column_index: Option<usize>,
+    pub paragraph_mode: bool,
+    pub project_key: Option<String>,
+    pub project_column_index: Option<usize>,
+    pub skip_empty_fields: bool,
+    pub combine_format: String,
+    pub json_root: Option<String>,
+    pub comment_key: Option<String>,
+    pub comment_column_index: Option<usize>,
+    pub encoding: Option<&'static encoding_rs::Encoding>,
+    pub relates_to_key: Option<String>,
+    pub relates_to_column_index: Option<usize>,
+    pub blocks_key: Option<String>,
+    pub blocks_column_index: Option<usize>,
+    pub blocked_by_key: Option<String>,
+    pub blocked_by_column_index: Option<usize>,
+    pub skip_rows: usize,
+    pub escape_char: Option<char>,
+    pub comment_char: Option<char>,
+    pub flexible: bool,
 }
 impl FileParser {
-    pub fn new(
-        file: PathBuf,
-        separator: Option<char>,
-        no_header: bool,
-        title_key: Option<String>,
-        title_column_index: Option<usize>,
-        description_key: Option<String>,
-        description_column_index: Option<usize>,
-        prepend_title: Option<String>,
-        combine_remaining: bool,
-    ) -> FileParser {
-        let file_extension = file.extension().unwrap().to_str().unwrap().to_lowercase();
+    pub fn new(options: ParserOptions) -> FileParser {
+        let ParserOptions {
+            file,
+            separator,
+            no_header,
+            title_key,
+            title_column_index,
+            description_key,
+            description_column_index,
+            prepend_title,
+            combine_remaining,
+            no_description,
+            labels_key,
+            labels_column_index,
+            due_date_key,
+            due_date_column_index,
+            confidential_key,
+            format,
+            weight_key,
+            weight_column_index,
+            sheet,
+            sheet_index,
+            skip_empty_titles,
+            estimate_key,
+            estimate_column_index,
+            detect_delimiter,
+            description_from_file,
+            issue_type_key,
+            issue_type_column_index,
+            lenient,
+            quote_char,
+            prepend_description,
+            append_description,
+            description_template,
+            strict_template,
+            label_separator,
+            filter_key,
+            filter_column_index,
+            filter_since,
+            filter_until,
+            strict,
+            description_keys,
+            description_column_indices,
+            assignee_key,
+            assignee_column_index,
+            min_columns,
+            case_sensitive_keys,
+            created_at_key,
+            created_at_column_index,
+            paragraph_mode,
+            project_key,
+            project_column_index,
+            skip_empty_fields,
+            combine_format,
+            json_root,
+            comment_key,
+            comment_column_index,
+            encoding,
+            relates_to_key,
+            relates_to_column_index,
+            blocks_key,
+            blocks_column_index,
+            blocked_by_key,
+            blocked_by_column_index,
+            skip_rows,
+            escape_char,
+            comment_char,
+            flexible,
+        } = options;
+        // The format overrides the file extension, e.g. when reading from stdin
+        let file_extension = match format {
+            Some(f) => f.to_lowercase(),
+            None => file.extension().unwrap().to_str().unwrap().to_lowercase(),
+        };
         FileParser {
-            file: file.clone(),
-            file_extension: file_extension,
-            separator: separator,
-            no_header: no_header,
-            title_key: title_key.clone(),
-            title_column_index: title_column_index,
-            description_key: description_key.clone(),
-            description_column_index: description_column_index,
-            prepend_title: prepend_title,
-            combine_remaining: combine_remaining,
+            file,
+            file_extension,
+            separator,
+            no_header,
+            title_key,
+            title_column_index,
+            description_key,
+            description_column_index,
+            prepend_title,
+            combine_remaining,
+            no_description,
+            labels_key,
+            labels_column_index,
+            due_date_key,
+            due_date_column_index,
+            confidential_key,
+            confidential_column_index: None,
+            weight_key,
+            weight_column_index,
+            sheet,
+            sheet_index,
+            skip_empty_titles,
+            estimate_key,
+            estimate_column_index,
+            detect_delimiter,
+            description_from_file,
+            issue_type_key,
+            issue_type_column_index,
+            lenient,
+            quote_char,
+            prepend_description,
+            append_description,
+            // Allow \n in templates given on the command line
+            description_template: description_template.map(|t| t.replace("\\n", "\n")),
+            strict_template,
+            label_separator,
+            filter_key,
+            filter_column_index,
+            filter_since,
+            filter_until,
+            strict,
+            description_keys,
+            description_column_indices,
+            assignee_key,
+            assignee_column_index,
+            min_columns,
+            case_sensitive_keys,
+            created_at_key,
+            created_at_column_index,
+            paragraph_mode,
+            project_key,
+            project_column_index,
+            skip_empty_fields,
+            combine_format,
+            json_root,
+            comment_key,
+            comment_column_index,
+            encoding,
+            relates_to_key,
+            relates_to_column_index,
+            blocks_key,
+            blocks_column_index,
+            blocked_by_key,
+            blocked_by_column_index,
+            skip_rows,
+            escape_char,
+            comment_char,
+            flexible,
         }
     }
     pub fn get_issues(&mut self) -> Result<Vec<IssueFromFile>, String> {
         match self.file_extension.as_str() {
-            "csv" => self.csv_to_issues(),
+            "csv" | "tsv" => self.csv_to_issues(),
             "json" => self.json_to_issues(),
-            _ => return Err(String::from("Unsupported file type")),
+            "jsonl" | "ndjson" => self.jsonl_to_issues(),
+            "yaml" | "yml" => self.yaml_to_issues(),
+            "xlsx" => self.xlsx_to_issues(),
+            "org" => self.org_to_issues(),
+            "md" => self.markdown_to_issues(),
+            "txt" => self.text_to_issues(),
+            _ => Err(String::from("Unsupported file type")),
+        }
+    }
+    /// Read the whole file, or stdin if the file is "-", into a string
+    fn read_contents(&self) -> Result<String, String> {
+        let mut contents = String::new();
+        if let Some(encoding) = self.encoding {
+            contents = self.read_encoded(encoding)?;
+        } else if self.file.as_os_str() == STDIN_FILE {
+            debug!("Reading issues from stdin");
+            if let Err(e) = std::io::stdin().read_to_string(&mut contents) {
+                return Err(format!("Could not read stdin: {}", e));
+            }
+        } else {
+            contents = match std::fs::read_to_string(&self.file) {
+                Ok(c) => c,
+                Err(e) => {
+                    return Err(format!(
+                        "Could not read file {}: {}",
+                        self.file.display(),
+                        e
+                    ))
+                }
+            };
+        }
+        // Excel and some editors start UTF-8 files with a byte order mark,
+        // which would otherwise end up in the first header or key
+        match contents.strip_prefix('\u{feff}') {
+            Some(stripped) => {
+                debug!("Removed UTF-8 byte order mark");
+                Ok(stripped.to_string())
+            }
+            None => Ok(contents),
+        }
+    }
+    /// Read the whole file, or stdin, and decode it from the given encoding to UTF-8
+    fn read_encoded(&self, encoding: &'static encoding_rs::Encoding) -> Result<String, String> {
+        let mut bytes: Vec<u8> = Vec::new();
+        let read = if self.file.as_os_str() == STDIN_FILE {
+            debug!("Reading issues from stdin");
+            std::io::stdin().read_to_end(&mut bytes)
+        } else {
+            std::fs::File::open(&self.file).and_then(|mut f| f.read_to_end(&mut bytes))
+        };
+        if let Err(e) = read {
+            return Err(format!(
+                "Could not read file {}: {}",
+                self.file.display(),
+                e
+            ));
+        }
+        // A byte order mark overrides the encoding, as browsers do
+        let (contents, used, had_errors) = encoding.decode(&bytes);
+        debug!("Decoded file from {}", used.name());
+        if had_errors {
+            return Err(format!(
+                "Could not decode file {} as {}, check --encoding",
+                self.file.display(),
+                used.name()
+            ));
+        }
+        Ok(contents.into_owned())
+    }
+    /// Read the description of an issue from the file the description column points to.
+    /// Relative paths are relative to the directory of the issues file.
+    fn read_description_file(&self, path: &str, row: &str) -> Result<Option<String>, String> {
+        let path = path.trim();
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let base = match self.file.as_os_str() == STDIN_FILE {
+            true => None,
+            false => self.file.parent(),
+        };
+        let full_path = match base {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        };
+        debug!(
+            "Reading description of {} from {}",
+            row,
+            full_path.display()
+        );
+        match std::fs::read_to_string(&full_path) {
+            Ok(d) => Ok(Some(d)),
+            Err(e) => Err(format!(
+                "Could not read description file '{}' on {}: {}",
+                full_path.display(),
+                row,
+                e
+            )),
         }
     }
     fn csv_to_issues(&mut self) -> Result<Vec<IssueFromFile>, String> {
         debug!("Parsing csv file with options: {:#?}", self);
+        let contents = self.read_contents()?;
+        // Drop the rows before the header, e.g. the name of the report and the export date.
+        // The byte order mark was already removed from the start of the file
+        let contents = skip_lines(&contents, self.skip_rows)?;
+        if self.detect_delimiter {
+            let delimiter = detect_delimiter(contents.lines().next().unwrap_or_default());
+            info!("Detected delimiter {:?}", delimiter);
+            self.separator = Some(delimiter);
+        } else if self.file_extension == "tsv" {
+            self.separator = Some('\t');
+        }
+        self.parse_csv_reader(contents.as_bytes())
+    }
+    /// Parse csv from any reader, e.g. the contents of the file.
+    /// The separator must already be known.
+    fn parse_csv_reader<R: Read>(&mut self, csv: R) -> Result<Vec<IssueFromFile>, String> {
+        // The csv reader only supports single byte separators, the first byte of a longer
+        // character would silently split the rows at the wrong place
+        let separator = match self.separator {
+            Some(s) if s.is_ascii() => s,
+            Some(s) => {
+                return Err(format!(
+                    "Invalid separator '{}', the separator must be a single ASCII character",
+                    s
+                ))
+            }
+            None => {
+                return Err(String::from(
+                    "No separator for the csv file, use --separator or --detect-delimiter",
+                ))
+            }
+        };
         // Open csv reader
+        // Rows with the wrong number of columns are checked below, so the reader can be flexible.
+        // Quoted fields may contain the separator, newlines and doubled or escaped quote characters.
+        // The characters were checked to be ASCII in verify_args
         let mut reader = ReaderBuilder::new()
             .has_headers(!self.no_header)
-            .delimiter(self.separator.unwrap().to_string().as_bytes()[0])
-            .from_path(&self.file)
-            .unwrap();
+            .delimiter(separator as u8)
+            .quote(self.quote_char as u8)
+            .escape(self.escape_char.map(|c| c as u8))
+            .comment(self.comment_char.map(|c| c as u8))
+            .flexible(true)
+            .from_reader(csv);
+        // Without a header row, the csv reader returns the first record as headers,
+        // which is still what we want for the bounds checks
+        let headers = match reader.headers() {
+            Ok(h) => h.clone(),
+            Err(_) => return Err(String::from("Could not read headers")),
+        };
+        // A wrong separator usually reads every line as a single column
+        if let Some(min_columns) = self.min_columns {
+            if headers.len() < min_columns {
+                return Err(format!(
+                    "The {} has {} columns, expected at least {}. Is the separator {:?} right?",
+                    match self.no_header {
+                        true => "first row",
+                        false => "header",
+                    },
+                    headers.len(),
+                    min_columns,
+                    separator
+                ));
+            }
+        }
+        let mut records: Vec<StringRecord> = Vec::new();
+        // Rows that can not be read or have the wrong number of columns
+        let mut malformed: Vec<String> = Vec::new();
+        for result in reader.records() {
+            let mut record = match result {
+                Ok(r) => r,
+                Err(e) => {
+                    malformed.push(format!("Could not read record: {}", e));
+                    continue;
+                }
+            };
+            // Missing columns are empty and extra columns are dropped with --flexible
+            if self.flexible {
+                record.truncate(headers.len());
+                while record.len() < headers.len() {
+                    record.push_field("");
+                }
+            }
+            if record.len() != headers.len() {
+                let raw: Vec<&str> = record.iter().collect();
+                malformed.push(format!(
+                    "line {} has {} columns instead of {}: {}",
+                    record.position().map(|p| p.line()).unwrap_or_default() + self.skip_rows as u64,
+                    record.len(),
+                    headers.len(),
+                    raw.join(&separator.to_string())
+                ));
+                continue;
+            }
+            records.push(record);
+        }
+        if !malformed.is_empty() {
+            if !self.lenient {
+                malformed.iter().for_each(|m| error!("{}", m));
+                return Err(format!(
+                    "{} rows could not be parsed, use --lenient to skip them or --flexible to accept rows with another number of columns",
+                    malformed.len()
+                ));
+            }
+            malformed.iter().for_each(|m| warn!("Skipping row, {}", m));
+        }
+        self.records_to_issues(&headers, records)
+    }
+    fn xlsx_to_issues(&mut self) -> Result<Vec<IssueFromFile>, String> {
+        debug!("Parsing xlsx file with options: {:#?}", self);
+        // Spreadsheets are binary, so they can't go through read_contents
+        let mut contents: Vec<u8> = Vec::new();
+        let read = if self.file.as_os_str() == STDIN_FILE {
+            debug!("Reading issues from stdin");
+            std::io::stdin().read_to_end(&mut contents)
+        } else {
+            std::fs::File::open(&self.file).and_then(|mut f| f.read_to_end(&mut contents))
+        };
+        if let Err(e) = read {
+            return Err(format!(
+                "Could not read file {}: {}",
+                self.file.display(),
+                e
+            ));
+        }
+        let mut workbook = match calamine::Xlsx::new(std::io::Cursor::new(contents)) {
+            Ok(w) => w,
+            Err(e) => return Err(format!("Could not open xlsx file: {}", e)),
+        };
+        // Use the chosen worksheet, or the first one
+        let sheet_names = workbook.sheet_names();
+        let sheet_name = match (self.sheet.as_ref(), self.sheet_index) {
+            (_, Some(i)) => match sheet_names.get(i) {
+                Some(name) => name.clone(),
+                None => {
+                    return Err(format!(
+                        "Sheet index {} is out of bounds, available sheets: {:?}",
+                        i, sheet_names
+                    ))
+                }
+            },
+            (Some(name), None) => match sheet_names.contains(name) {
+                true => name.clone(),
+                false => {
+                    return Err(format!(
+                        "Sheet '{}' not found, available sheets: {:?}",
+                        name, sheet_names
+                    ))
+                }
+            },
+            (None, None) => match sheet_names.first() {
+                Some(name) => name.clone(),
+                None => return Err(String::from("xlsx file has no worksheets")),
+            },
+        };
+        debug!("Reading worksheet '{}'", sheet_name);
+        let range = match workbook.worksheet_range(&sheet_name) {
+            Ok(r) => r,
+            Err(e) => return Err(format!("Could not read worksheet: {}", e)),
+        };
+        // Line numbers in errors should match the row numbers shown in the spreadsheet
+        let first_row = range.start().map(|(row, _)| row).unwrap_or(0);
+        // Turn the rows into csv records, so the rest of the parsing is shared with csv files.
+        // Rows without any values, e.g. formatted but empty rows at the end of the sheet, are skipped
+        let mut rows = range
+            .rows()
+            .enumerate()
+            .filter(|(_, row)| row.iter().any(|c| !cell_to_string(c).trim().is_empty()))
+            .map(|(i, row)| {
+                let mut record: StringRecord = row.iter().map(cell_to_string).collect();
+                let mut position = csv::Position::new();
+                position.set_line(first_row as u64 + i as u64 + 1);
+                record.set_position(Some(position));
+                record
+            });
+        let headers = match rows.next() {
+            Some(h) => h,
+            None => return Err(String::from("xlsx worksheet is empty")),
+        };
+        let mut records: Vec<StringRecord> = Vec::new();
+        if self.no_header {
+            records.push(headers.clone());
+        }
+        records.extend(rows);
+        self.records_to_issues(&headers, records)
+    }
+    /// Build issues from csv records, used for both csv and xlsx files.
+    /// headers is the header row, or the first record if the file has no header row.
+    fn records_to_issues(
+        &mut self,
+        headers: &StringRecord,
+        records: Vec<StringRecord>,
+    ) -> Result<Vec<IssueFromFile>, String> {
         // Get title and description column index
         let mut all_headers: Vec<String> = Vec::new(); // Used if combine_remaining is set
         if !self.no_header {
-            let headers = match reader.headers() {
-                Ok(h) => h,
-                Err(_) => return Err(String::from("Could not read headers")),
-            };
-            debug!("CSV file has headers {:?}", headers);
+            debug!("File has headers {:?}", headers);
             // Get title column index if title_column is set by name
-            if self.title_key.is_some() {
-                debug!(
-                    "User specified title_column: '{}', trying to find column index...",
-                    self.title_key.as_ref().unwrap()
-                );
-                // Get index of title_column, match any case
-                headers
-                    .iter()
-                    .position(|x| {
-                        x.to_lowercase() == self.title_key.as_ref().unwrap().to_lowercase().as_str()
-                    })
-                    .map(|i| self.title_column_index = Some(i));
-                match self.title_column_index {
-                    Some(i) => debug!("Found title_column_index: {}", i),
-                    None => {
-                        return Err(format!(
-                            "Could not find column with name '{}'",
-                            self.title_key.as_ref().unwrap()
-                        ))
-                    }
-                }
+            if let Some(title_key) = self.title_key.as_ref() {
+                self.title_column_index = Some(find_column_index(
+                    headers,
+                    title_key,
+                    self.case_sensitive_keys,
+                )?);
             }
             if self.combine_remaining {
                 headers.iter().for_each(|x| all_headers.push(x.to_string()));
             }
             // Get description column index if description_column is set by name
-            if self.description_key.is_some() & !self.combine_remaining {
-                debug!(
-                    "User specified description_column: '{}', trying to find column index...",
-                    self.description_key.as_ref().unwrap()
+            if let (Some(description_key), false) = (
+                self.description_key.as_ref(),
+                self.combine_remaining || self.description_keys.is_some(),
+            ) {
+                self.description_column_index = Some(find_column_index(
+                    headers,
+                    description_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get the indices of the description columns if they are set by name
+            if let Some(description_keys) = self.description_keys.as_ref() {
+                self.description_column_indices = Some(
+                    description_keys
+                        .iter()
+                        .map(|key| find_column_index(headers, key, self.case_sensitive_keys))
+                        .collect::<Result<Vec<usize>, String>>()?,
                 );
-                // Get index of description_column, match any case
-                headers
-                    .iter()
-                    .position(|x| {
-                        x.to_lowercase()
-                            == self
-                                .description_key
-                                .as_ref()
-                                .unwrap()
-                                .to_lowercase()
-                                .as_str()
-                    })
-                    .map(|i| self.description_column_index = Some(i));
-                match self.description_column_index {
-                    Some(i) => debug!("Found description_column_index: {}", i),
-                    None => {
-                        return Err(format!(
-                            "Could not find column with name '{}'",
-                            self.description_key.as_ref().unwrap()
-                        ))
-                    }
-                }
+            }
+            // Get labels column index if labels_column is set by name
+            if let Some(labels_key) = self.labels_key.as_ref() {
+                self.labels_column_index = Some(find_column_index(
+                    headers,
+                    labels_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get due date column index if due_date_column is set by name
+            if let Some(due_date_key) = self.due_date_key.as_ref() {
+                self.due_date_column_index = Some(find_column_index(
+                    headers,
+                    due_date_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get confidential column index if confidential_column is set by name
+            if let Some(confidential_key) = self.confidential_key.as_ref() {
+                self.confidential_column_index = Some(find_column_index(
+                    headers,
+                    confidential_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get weight column index if weight_column is set by name
+            if let Some(weight_key) = self.weight_key.as_ref() {
+                self.weight_column_index = Some(find_column_index(
+                    headers,
+                    weight_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get estimate column index if estimate_column is set by name
+            if let Some(estimate_key) = self.estimate_key.as_ref() {
+                self.estimate_column_index = Some(find_column_index(
+                    headers,
+                    estimate_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get issue type column index if issue_type_column is set by name
+            if let Some(issue_type_key) = self.issue_type_key.as_ref() {
+                self.issue_type_column_index = Some(find_column_index(
+                    headers,
+                    issue_type_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get assignee column index if assignee_column is set by name
+            if let Some(assignee_key) = self.assignee_key.as_ref() {
+                self.assignee_column_index = Some(find_column_index(
+                    headers,
+                    assignee_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get created at column index if created_at_column is set by name
+            if let Some(created_at_key) = self.created_at_key.as_ref() {
+                self.created_at_column_index = Some(find_column_index(
+                    headers,
+                    created_at_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get project column index if project_column is set by name
+            if let Some(project_key) = self.project_key.as_ref() {
+                self.project_column_index = Some(find_column_index(
+                    headers,
+                    project_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get comment column index if comment_column is set by name
+            if let Some(comment_key) = self.comment_key.as_ref() {
+                self.comment_column_index = Some(find_column_index(
+                    headers,
+                    comment_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get link column indexes if the link columns are set by name
+            if let Some(relates_to_key) = self.relates_to_key.as_ref() {
+                self.relates_to_column_index = Some(find_column_index(
+                    headers,
+                    relates_to_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            if let Some(blocks_key) = self.blocks_key.as_ref() {
+                self.blocks_column_index = Some(find_column_index(
+                    headers,
+                    blocks_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            if let Some(blocked_by_key) = self.blocked_by_key.as_ref() {
+                self.blocked_by_column_index = Some(find_column_index(
+                    headers,
+                    blocked_by_key,
+                    self.case_sensitive_keys,
+                )?);
+            }
+            // Get filter column index if filter_column is set by name
+            if let Some(filter_key) = self.filter_key.as_ref() {
+                self.filter_column_index = Some(find_column_index(
+                    headers,
+                    filter_key,
+                    self.case_sensitive_keys,
+                )?);
             }
             if self.combine_remaining {
                 debug!("User specified to combine remaining columns");
@@ -141,60 +792,272 @@ impl FileParser {
         }
         // Are title_column_index and description_column_index within bounds?
         // We dont need to check if title_column_index is Some, because we would have returned already
-        if self.title_column_index.unwrap() >= reader.headers().unwrap().len() {
+        if self.title_column_index.unwrap() >= headers.len() {
             return Err(String::from("title_column_index is out of bounds"));
         }
         // We need to check if description_column_index is Some, because it is optional
-        if self.description_column_index.is_some() {
-            if self.description_column_index.unwrap() >= reader.headers().unwrap().len() {
-                return Err(String::from("description_column_index is out of bounds"));
+        if self.description_column_index.is_some()
+            && self.description_column_index.unwrap() >= headers.len()
+        {
+            return Err(String::from("description_column_index is out of bounds"));
+        }
+        if let Some(indices) = self.description_column_indices.as_ref() {
+            if indices.iter().any(|i| *i >= headers.len()) {
+                return Err(String::from("description_column_indices is out of bounds"));
+            }
+        }
+        if let Some(i) = self.labels_column_index {
+            if i >= headers.len() {
+                return Err(String::from("labels_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.due_date_column_index {
+            if i >= headers.len() {
+                return Err(String::from("due_date_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.weight_column_index {
+            if i >= headers.len() {
+                return Err(String::from("weight_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.estimate_column_index {
+            if i >= headers.len() {
+                return Err(String::from("estimate_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.issue_type_column_index {
+            if i >= headers.len() {
+                return Err(String::from("issue_type_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.assignee_column_index {
+            if i >= headers.len() {
+                return Err(String::from("assignee_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.created_at_column_index {
+            if i >= headers.len() {
+                return Err(String::from("created_at_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.project_column_index {
+            if i >= headers.len() {
+                return Err(String::from("project_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.comment_column_index {
+            if i >= headers.len() {
+                return Err(String::from("comment_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.relates_to_column_index {
+            if i >= headers.len() {
+                return Err(String::from("relates_to_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.blocks_column_index {
+            if i >= headers.len() {
+                return Err(String::from("blocks_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.blocked_by_column_index {
+            if i >= headers.len() {
+                return Err(String::from("blocked_by_column_index is out of bounds"));
+            }
+        }
+        if let Some(i) = self.filter_column_index {
+            if i >= headers.len() {
+                return Err(String::from("filter_column_index is out of bounds"));
             }
         }
         // We now have valid title_column_index and if set, description_column_index as well
         // Start building issues
         let mut issues: Vec<IssueFromFile> = Vec::new();
         // Step through the records
-        for result in reader.records() {
-            let record = match result {
-                Ok(r) => r,
-                Err(_) => {
-                    error!("Error reading record: {:#?}", result);
-                    return Err(String::from("Could not read record"));
-                }
+        for (n, record) in records.into_iter().enumerate() {
+            // Used to point at the record in error messages
+            let row = match record.position() {
+                Some(p) => format!("line {}", p.line() + self.skip_rows as u64),
+                None => format!("record {}", n + 1),
             };
+            // Drop records outside of --filter-since and --filter-until
+            if let Some(i) = self.filter_column_index {
+                if !self.in_date_range(record.get(i).unwrap_or_default(), &row)? {
+                    debug!("Skipping {}, its date is outside of the filter range", row);
+                    continue;
+                }
+            }
             // Get title
             let title = match record.get(self.title_column_index.unwrap()) {
                 Some(t) => t.to_string(),
                 None => return Err(String::from("Could not get title")),
             };
+            if title.trim().is_empty() {
+                match self.skip_empty_titles {
+                    true => {
+                        debug!("Skipping {}, it has an empty title", row);
+                        continue;
+                    }
+                    false => return Err(empty_title_error(&row)),
+                }
+            }
             // Get description
             let mut description: Option<String> = None;
-            if self.combine_remaining {
+            if let Some(indices) = self.description_column_indices.as_ref() {
+                // Combine the chosen columns into description, in the given order
+                let mut fields: Vec<(String, String)> = Vec::new();
+                for i in indices {
+                    let key = match self.no_header {
+                        true => format!("Column {}", i),
+                        false => headers.get(*i).unwrap_or_default().to_string(),
+                    };
+                    let field = record.get(*i).unwrap_or_default();
+                    fields.push((key.trim().to_string(), field.to_string()));
+                }
+                description = Some(combine_fields(&fields, &self.combine_format));
+            } else if self.combine_remaining {
                 // Combine remaining columns into description
-                let mut description_string = String::new();
+                let mut fields: Vec<(String, String)> = Vec::new();
                 for (i, field) in record.iter().enumerate() {
-                    if i == self.title_column_index.unwrap() {
+                    if i == self.title_column_index.unwrap()
+                        || Some(i) == self.labels_column_index
+                        || Some(i) == self.due_date_column_index
+                        || Some(i) == self.confidential_column_index
+                        || Some(i) == self.weight_column_index
+                        || Some(i) == self.estimate_column_index
+                        || Some(i) == self.issue_type_column_index
+                        || Some(i) == self.assignee_column_index
+                        || Some(i) == self.created_at_column_index
+                        || Some(i) == self.project_column_index
+                        || Some(i) == self.comment_column_index
+                        || self.link_columns().iter().any(|(_, c)| *c == Some(i))
+                        || (self.skip_empty_fields && field.trim().is_empty())
+                    {
                         continue;
                     }
                     let key = match self.no_header {
                         true => format!("Column {}", i),
-                        false => format!("{}", all_headers[i]),
+                        false => all_headers[i].to_string(),
                     };
 
-                    description_string.push_str(&format!(
-                        "{}: {}\n\n",
-                        key.trim(),
-                        field.to_string()
-                    ));
+                    fields.push((key.trim().to_string(), field.to_string()));
                 }
-                description = Some(description_string);
-            } else if self.description_column_index.is_some() {
+                description = Some(combine_fields(&fields, &self.combine_format));
+            } else if let Some(template) = self.description_template.as_ref() {
+                // Fill the template with the fields of this record
+                let lookup = |name: &str| {
+                    let i = match self.no_header {
+                        true => name.parse::<usize>().ok(),
+                        false => headers
+                            .iter()
+                            .position(|h| keys_match(h, name, self.case_sensitive_keys)),
+                    };
+                    i.and_then(|i| record.get(i)).map(|v| v.to_string())
+                };
+                description = Some(expand_template(
+                    template,
+                    lookup,
+                    self.strict_template,
+                    &row,
+                )?);
+            } else if let Some(i) = self.description_column_index {
                 // Get description from column
-                description = match record.get(self.description_column_index.unwrap()) {
+                description = match record.get(i) {
+                    Some(d) if self.description_from_file => self.read_description_file(d, &row)?,
                     Some(d) => Some(d.to_string()),
                     None => return Err(String::from("Could not get description")),
                 };
             }
+            // Get labels
+            let mut labels: Option<String> = None;
+            if let Some(i) = self.labels_column_index {
+                labels = match record.get(i) {
+                    Some(l) if l.trim().is_empty() => None,
+                    Some(l) => Some(normalize_labels(l, self.label_separator, &row)?),
+                    None => return Err(String::from("Could not get labels")),
+                };
+            }
+            // Get due date
+            let mut due_date: Option<String> = None;
+            if let Some(i) = self.due_date_column_index {
+                due_date = match record.get(i) {
+                    Some(d) => validate_due_date(d, &row)?,
+                    None => return Err(String::from("Could not get due date")),
+                };
+            }
+            // Get confidential
+            let confidential = match self.confidential_column_index {
+                Some(i) => record.get(i).is_some_and(is_truthy),
+                None => false,
+            };
+            // Get weight
+            let mut weight: Option<u32> = None;
+            if let Some(i) = self.weight_column_index {
+                weight = match record.get(i) {
+                    Some(w) => parse_weight(w, &row)?,
+                    None => return Err(String::from("Could not get weight")),
+                };
+            }
+            // Get estimate
+            let mut estimate: Option<String> = None;
+            if let Some(i) = self.estimate_column_index {
+                estimate = match record.get(i) {
+                    Some(e) => parse_estimate(e, &row)?,
+                    None => return Err(String::from("Could not get estimate")),
+                };
+            }
+            // Get issue type
+            let mut issue_type: Option<String> = None;
+            if let Some(i) = self.issue_type_column_index {
+                issue_type = match record.get(i) {
+                    Some(t) => parse_issue_type(t, &row)?,
+                    None => return Err(String::from("Could not get issue type")),
+                };
+            }
+            // Get assignee
+            let mut assignee: Option<String> = None;
+            if let Some(i) = self.assignee_column_index {
+                assignee = match record.get(i) {
+                    Some(a) => parse_assignee(a),
+                    None => return Err(String::from("Could not get assignee")),
+                };
+            }
+            // Get created at
+            let mut created_at: Option<String> = None;
+            if let Some(i) = self.created_at_column_index {
+                created_at = match record.get(i) {
+                    Some(c) => parse_created_at(c, &row)?,
+                    None => return Err(String::from("Could not get created at")),
+                };
+            }
+            // Get project
+            let mut project: Option<String> = None;
+            if let Some(i) = self.project_column_index {
+                project = match record.get(i) {
+                    Some(p) => parse_project(p),
+                    None => return Err(String::from("Could not get project")),
+                };
+            }
+            // Get comment
+            let mut comment: Option<String> = None;
+            if let Some(i) = self.comment_column_index {
+                comment = match record.get(i) {
+                    Some(c) if c.trim().is_empty() => None,
+                    Some(c) => Some(c.to_string()),
+                    None => return Err(String::from("Could not get comment")),
+                };
+            }
+            // Get the titles of the issues to link to
+            let mut links: Vec<(&'static str, String)> = Vec::new();
+            for (link_type, i) in self.link_columns() {
+                if let Some(i) = i {
+                    match record.get(i) {
+                        Some(l) => links.extend(self.parse_links(l, link_type)),
+                        None => return Err(String::from("Could not get links")),
+                    }
+                }
+            }
 
             // Build issue and push it to issues
             let issue = IssueFromFile {
@@ -202,7 +1065,18 @@ impl FileParser {
                     Some(p) => format!("{} {}", p, title),
                     None => title,
                 },
-                description: description,
+                description: self.decorate_description(description),
+                labels,
+                due_date,
+                confidential,
+                weight,
+                estimate,
+                issue_type,
+                assignee,
+                created_at,
+                project,
+                comment,
+                links,
             };
             issues.push(issue);
         }
@@ -211,27 +1085,34 @@ impl FileParser {
     }
     fn json_to_issues(&self) -> Result<Vec<IssueFromFile>, String> {
         debug!("Parsing json file with options: {:#?}", self);
-        let mut issues: Vec<IssueFromFile> = Vec::new();
         // Read json file to string and parse it
-        let mut contents = match std::fs::read_to_string(&self.file) {
-            Ok(c) => c,
-            Err(e) => return Err(format!("Could not read file: {}", e)),
-        };
-        let data: serde_json::Value = match serde_json::from_str(&contents) {
+        let contents = self.read_contents()?;
+        self.parse_json_str(&contents)
+    }
+    /// Parse issues from a json array of objects, or a single object
+    fn parse_json_str(&self, contents: &str) -> Result<Vec<IssueFromFile>, String> {
+        let mut issues: Vec<IssueFromFile> = Vec::new();
+        let data: serde_json::Value = match serde_json::from_str(contents) {
             Ok(j) => j,
             Err(e) => return Err(format!("Could not parse json: {}", e)),
         };
+        // The issues can be nested in a wrapper object, e.g. {"data": {"issues": [...]}}
+        let data = match self.json_root.as_ref() {
+            Some(root) => find_json_root(&data, root, self.case_sensitive_keys)?,
+            None => &data,
+        };
         // Check if data is an array of objects
         debug!("Json data: {:#?}", data);
         if data.is_array() {
-            for item in data.as_array().unwrap() {
+            for (i, item) in data.as_array().unwrap().iter().enumerate() {
                 debug!("Item: {:#?}", item);
                 if item.is_object() {
-                    let issue = match self.serde_object_to_issue(item.as_object().unwrap()) {
-                        Ok(i) => i,
-                        Err(e) => return Err(e),
-                    };
-                    issues.push(issue);
+                    let row = format!("item {}", i + 1);
+                    if let Some(issue) =
+                        self.serde_object_to_issue(item.as_object().unwrap(), &row)?
+                    {
+                        issues.push(issue);
+                    }
                 } else {
                     return Err(String::from(
                         "Json data is not of a format that can be parsed",
@@ -239,11 +1120,9 @@ impl FileParser {
                 }
             }
         } else if data.is_object() {
-            let issue = match self.serde_object_to_issue(data.as_object().unwrap()) {
-                Ok(i) => i,
-                Err(e) => return Err(e),
-            };
-            issues.push(issue);
+            if let Some(issue) = self.serde_object_to_issue(data.as_object().unwrap(), "item 1")? {
+                issues.push(issue);
+            }
         } else {
             return Err(String::from(
                 "Json data is not of a format that can be parsed",
@@ -252,54 +1131,1896 @@ impl FileParser {
 
         Ok(issues)
     }
+    fn jsonl_to_issues(&self) -> Result<Vec<IssueFromFile>, String> {
+        debug!("Parsing json lines file with options: {:#?}", self);
+        let mut issues: Vec<IssueFromFile> = Vec::new();
+        let contents = self.read_contents()?;
+        // Every non-empty line is a json object
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let item: serde_json::Value = match serde_json::from_str(line) {
+                Ok(j) => j,
+                Err(e) => return Err(format!("Could not parse json on line {}: {}", i + 1, e)),
+            };
+            debug!("Item: {:#?}", item);
+            match item.as_object() {
+                Some(object) => {
+                    let row = format!("line {}", i + 1);
+                    if let Some(issue) = self.serde_object_to_issue(object, &row)? {
+                        issues.push(issue);
+                    }
+                }
+                None => {
+                    return Err(format!(
+                        "Json data on line {} is not an object that can be parsed",
+                        i + 1
+                    ))
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+    fn yaml_to_issues(&self) -> Result<Vec<IssueFromFile>, String> {
+        debug!("Parsing yaml file with options: {:#?}", self);
+        let mut issues: Vec<IssueFromFile> = Vec::new();
+        // Read yaml file to string and parse it
+        let contents = self.read_contents()?;
+        let yaml_data: serde_yaml::Value = match serde_yaml::from_str(&contents) {
+            Ok(y) => y,
+            Err(e) => return Err(format!("Could not parse yaml: {}", e)),
+        };
+        // Convert the yaml into serde_json values, so we can reuse serde_object_to_issue
+        let data: serde_json::Value = match serde_json::to_value(yaml_data) {
+            Ok(j) => j,
+            Err(e) => return Err(format!("Could not convert yaml: {}", e)),
+        };
+        // Check if data is a list of mappings
+        debug!("Yaml data: {:#?}", data);
+        if data.is_array() {
+            for (i, item) in data.as_array().unwrap().iter().enumerate() {
+                debug!("Item: {:#?}", item);
+                if item.is_object() {
+                    let row = format!("item {}", i + 1);
+                    if let Some(issue) =
+                        self.serde_object_to_issue(item.as_object().unwrap(), &row)?
+                    {
+                        issues.push(issue);
+                    }
+                } else {
+                    return Err(String::from(
+                        "Yaml data is not of a format that can be parsed",
+                    ));
+                }
+            }
+        } else if data.is_object() {
+            if let Some(issue) = self.serde_object_to_issue(data.as_object().unwrap(), "item 1")? {
+                issues.push(issue);
+            }
+        } else {
+            return Err(String::from(
+                "Yaml data is not of a format that can be parsed",
+            ));
+        }
+
+        Ok(issues)
+    }
+    /// One issue per line, the line is the title. Empty lines and comments starting with # are skipped.
+    /// With paragraph_mode, one issue per block of lines separated by empty lines,
+    /// the first line is the title and the other lines the description
+    fn text_to_issues(&self) -> Result<Vec<IssueFromFile>, String> {
+        debug!("Parsing text file with options: {:#?}", self);
+        let contents = self.read_contents()?;
+        Ok(self.parse_text_str(&contents))
+    }
+    fn parse_text_str(&self, contents: &str) -> Vec<IssueFromFile> {
+        if self.paragraph_mode {
+            return self.parse_paragraphs_str(contents);
+        }
+        contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|title| self.text_issue(title, None))
+            .collect()
+    }
+    fn parse_paragraphs_str(&self, contents: &str) -> Vec<IssueFromFile> {
+        let mut issues = Vec::new();
+        let mut block: Vec<&str> = Vec::new();
+        // A trailing empty line ends the last block
+        for line in contents.lines().chain(std::iter::once("")) {
+            if !line.trim().is_empty() {
+                block.push(line.trim_end());
+                continue;
+            }
+            if let Some((title, rest)) = block.split_first() {
+                let description = rest.join("\n");
+                let description = match description.trim() {
+                    "" => None,
+                    _ => Some(description),
+                };
+                issues.push(self.text_issue(title.trim(), description));
+            }
+            block.clear();
+        }
+        issues
+    }
+    fn text_issue(&self, title: &str, description: Option<String>) -> IssueFromFile {
+        IssueFromFile {
+            title: match self.prepend_title.as_ref() {
+                Some(p) => format!("{} {}", p, title),
+                None => title.to_string(),
+            },
+            description: self.decorate_description(description),
+            labels: None,
+            due_date: None,
+            confidential: false,
+            weight: None,
+            estimate: None,
+            issue_type: None,
+            assignee: None,
+            created_at: None,
+            project: None,
+            comment: None,
+            links: Vec::new(),
+        }
+    }
+    fn org_to_issues(&self) -> Result<Vec<IssueFromFile>, String> {
+        debug!("Parsing org file with options: {:#?}", self);
+        let contents = self.read_contents()?;
+        self.parse_org_str(&contents)
+    }
+    /// Parse issues from the top-level TODO and NEXT headlines of an org-mode file.
+    /// The text under a headline, up to the next top-level headline, is the description
+    /// and the tags of the headline, e.g. :bug:backend:, are the labels.
+    /// DONE and other headlines are skipped.
+    fn parse_org_str(&self, contents: &str) -> Result<Vec<IssueFromFile>, String> {
+        let mut issues: Vec<IssueFromFile> = Vec::new();
+        // Title, labels, body lines and line number of the current TODO headline
+        let mut current: Option<(String, Option<String>, Vec<&str>, usize)> = None;
+        for (i, line) in contents.lines().enumerate() {
+            if let Some(headline) = line.strip_prefix("* ") {
+                if let Some(entry) = current.take() {
+                    issues.extend(self.org_entry_to_issue(entry)?);
+                }
+                let (keyword, rest) = headline
+                    .trim_start()
+                    .split_once(' ')
+                    .unwrap_or((headline.trim(), ""));
+                if !ORG_TODO_KEYWORDS.contains(&keyword) {
+                    debug!("Skipping headline on line {}: {}", i + 1, headline);
+                    continue;
+                }
+                let (title, labels) = split_org_tags(rest, self.label_separator);
+                current = Some((title, labels, Vec::new(), i + 1));
+            } else if let Some((_, _, body, _)) = current.as_mut() {
+                body.push(line);
+            }
+        }
+        if let Some(entry) = current.take() {
+            issues.extend(self.org_entry_to_issue(entry)?);
+        }
+        Ok(issues)
+    }
+    /// Build an issue from a TODO headline of an org-mode file.
+    /// Returns None if the issue is skipped because of an empty title.
+    fn org_entry_to_issue(
+        &self,
+        (title, labels, body, line): (String, Option<String>, Vec<&str>, usize),
+    ) -> Result<Option<IssueFromFile>, String> {
+        let row = format!("line {}", line);
+        if title.is_empty() {
+            match self.skip_empty_titles {
+                true => {
+                    debug!("Skipping {}, it has an empty title", row);
+                    return Ok(None);
+                }
+                false => return Err(empty_title_error(&row)),
+            }
+        }
+        let labels = match labels {
+            Some(l) => Some(normalize_labels(
+                &l,
+                self.label_separator,
+                &format!("issue '{}'", title),
+            )?),
+            None => None,
+        };
+        let description = body.join("\n").trim().to_string();
+        Ok(Some(IssueFromFile {
+            title: match self.prepend_title.as_ref() {
+                Some(p) => format!("{} {}", p, title),
+                None => title,
+            },
+            description: self.decorate_description(match description.is_empty() {
+                true => None,
+                false => Some(description),
+            }),
+            labels,
+            due_date: None,
+            confidential: false,
+            weight: None,
+            estimate: None,
+            issue_type: None,
+            assignee: None,
+            created_at: None,
+            project: None,
+            comment: None,
+            links: Vec::new(),
+        }))
+    }
+    fn markdown_to_issues(&self) -> Result<Vec<IssueFromFile>, String> {
+        debug!("Parsing markdown file with options: {:#?}", self);
+        let contents = self.read_contents()?;
+        self.parse_markdown_str(&contents)
+    }
+    /// Parse issues from the unchecked top-level tasks of a markdown checklist, e.g. "- [ ] task1".
+    /// The indented lines under a task are the description, they can start with a YAML
+    /// front-matter block between --- lines with the labels, weight, estimate and assignee.
+    /// Checked tasks, e.g. "- [x] task2", are skipped.
+    fn parse_markdown_str(&self, contents: &str) -> Result<Vec<IssueFromFile>, String> {
+        let mut issues: Vec<IssueFromFile> = Vec::new();
+        // Title, body lines and line number of the current unchecked task
+        let mut current: Option<(String, Vec<&str>, usize)> = None;
+        for (i, line) in contents.lines().enumerate() {
+            // Empty and indented lines belong to the current task
+            if line.trim().is_empty() || line.starts_with([' ', '\t']) {
+                if let Some((_, body, _)) = current.as_mut() {
+                    body.push(line);
+                }
+                continue;
+            }
+            if let Some(entry) = current.take() {
+                issues.extend(self.markdown_task_to_issue(entry)?);
+            }
+            if let Some(title) = MARKDOWN_TASKS.iter().find_map(|t| line.strip_prefix(t)) {
+                current = Some((title.trim().to_string(), Vec::new(), i + 1));
+            } else if MARKDOWN_DONE_TASKS.iter().any(|t| line.starts_with(t)) {
+                debug!("Skipping checked task on line {}: {}", i + 1, line);
+            }
+        }
+        if let Some(entry) = current.take() {
+            issues.extend(self.markdown_task_to_issue(entry)?);
+        }
+        Ok(issues)
+    }
+    /// Build an issue from an unchecked task of a markdown checklist.
+    /// Returns None if the issue is skipped because of an empty title.
+    fn markdown_task_to_issue(
+        &self,
+        (title, body, line): (String, Vec<&str>, usize),
+    ) -> Result<Option<IssueFromFile>, String> {
+        let row = format!("line {}", line);
+        if title.is_empty() {
+            match self.skip_empty_titles {
+                true => {
+                    debug!("Skipping {}, it has an empty title", row);
+                    return Ok(None);
+                }
+                false => return Err(empty_title_error(&row)),
+            }
+        }
+        // Remove the indentation shared by the lines of the body
+        let indent = body
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let body: Vec<&str> = body
+            .iter()
+            .map(|l| l.get(indent..).unwrap_or("").trim_end())
+            .collect();
+        let (front_matter, body) = split_front_matter(&body, &row)?;
+        let mut issue = IssueFromFile {
+            title: match self.prepend_title.as_ref() {
+                Some(p) => format!("{} {}", p, title),
+                None => title,
+            },
+            description: self.decorate_description(match body.trim() {
+                "" => None,
+                _ => Some(body.trim().to_string()),
+            }),
+            labels: None,
+            due_date: None,
+            confidential: false,
+            weight: None,
+            estimate: None,
+            issue_type: None,
+            assignee: None,
+            created_at: None,
+            project: None,
+            comment: None,
+            links: Vec::new(),
+        };
+        for (key, value) in front_matter {
+            let text = match key.as_str() {
+                // Labels can be given as a list, e.g. [bug, backend], or separated by --label-separator
+                "labels" if value.is_array() => value
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(value_to_string)
+                    .collect::<Option<Vec<String>>>()
+                    .map(|labels| labels.join(&self.label_separator.to_string())),
+                _ => value_to_string(&value),
+            };
+            let text = match text {
+                Some(t) => t,
+                None => {
+                    return Err(format!(
+                        "The front-matter key '{}' on {} has {}, expected a value",
+                        key,
+                        row,
+                        json_type_name(&value)
+                    ))
+                }
+            };
+            match key.as_str() {
+                "labels" => {
+                    issue.labels = Some(normalize_labels(&text, self.label_separator, &row)?)
+                }
+                "weight" => issue.weight = parse_weight(&text, &row)?,
+                "estimate" => issue.estimate = parse_estimate(&text, &row)?,
+                "assignee" => issue.assignee = parse_assignee(&text),
+                _ => warn!(
+                    "Ignoring the front-matter key '{}' on {}, expected one of {}",
+                    key,
+                    row,
+                    MARKDOWN_FRONT_MATTER_KEYS.join(", ")
+                ),
+            }
+        }
+        Ok(Some(issue))
+    }
+    /// Build an issue from a json or yaml object.
+    /// row points at the object in error messages, e.g. "item 2" or "line 3".
+    /// Returns None if the issue is skipped because of an empty title.
     fn serde_object_to_issue(
         &self,
         data: &serde_json::Map<String, serde_json::Value>,
-    ) -> Result<IssueFromFile, String> {
+        row: &str,
+    ) -> Result<Option<IssueFromFile>, String> {
         // Loop through the keys and check if they are valid
-        let mut title: String = String::new();
+        let mut title: Option<String> = None;
         let mut description_string: Vec<String> = Vec::new();
-        let our_title_name = self.title_key.as_ref().unwrap().to_lowercase();
+        // Keys and values combined into the description with combine_remaining
+        let mut combined_fields: Vec<(String, String)> = Vec::new();
+        let mut labels: Option<String> = None;
+        let mut due_date: Option<String> = None;
+        let mut confidential = false;
+        let mut weight: Option<String> = None;
+        let mut estimate: Option<String> = None;
+        let mut issue_type: Option<String> = None;
+        let mut assignee: Option<String> = None;
+        let mut created_at: Option<String> = None;
+        let mut project: Option<String> = None;
+        let mut comment: Option<String> = None;
+        let mut links: Vec<(&'static str, String)> = Vec::new();
+        let our_title_name = self.title_key.as_ref().unwrap();
+        if !self.case_sensitive_keys {
+            [self.title_key.as_ref(), self.description_key.as_ref()]
+                .into_iter()
+                .flatten()
+                .for_each(|name| warn_ambiguous_keys(data.keys().map(|k| k.as_str()), name, row));
+        }
+        // Keys like meta.summary point into nested objects, they are read after the loop
+        let title_path = self
+            .title_key
+            .as_ref()
+            .filter(|k| is_path(data, k, self.case_sensitive_keys));
+        let description_path = match self.combine_remaining {
+            true => None,
+            false => self
+                .description_key
+                .as_ref()
+                .filter(|k| is_path(data, k, self.case_sensitive_keys)),
+        };
+        let filter_path = self
+            .filter_key
+            .as_ref()
+            .filter(|k| is_path(data, k, self.case_sensitive_keys));
+        let description_paths = self
+            .description_keys
+            .iter()
+            .flatten()
+            .filter(|k| is_path(data, k, self.case_sensitive_keys));
+        // The nested objects the paths point into
+        let path_roots: Vec<&String> = [title_path, description_path, filter_path]
+            .into_iter()
+            .flatten()
+            .chain(description_paths)
+            .collect();
 
         // let our_description_name = self.description_key.as_ref().unwrap().to_lowercase();
         for (key, value) in data {
-            let val = match value {
-                serde_json::Value::String(s) => s.to_string(),
-                serde_json::Value::Bool(b) => b.to_string(),
-                serde_json::Value::Number(n) => n.to_string(),
-                serde_json::Value::Null => String::from("null"),
-                _ => return Err(String::from("Title is not a string")),
+            let val = match value_to_string(value) {
+                Some(v) => v,
+                None if path_roots
+                    .iter()
+                    .any(|p| is_path_root(key, p, self.case_sensitive_keys)) =>
+                {
+                    continue
+                }
+                // Labels can be given as a list, e.g. ["bug", "urgent"], or as a list of
+                // objects with a name like in GitHub exports, e.g. [{"name": "bug"}]
+                None if key_matches(key, &self.labels_key, self.case_sensitive_keys)
+                    && value.is_array() =>
+                {
+                    let labels: Option<Vec<String>> = value
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|label| value_to_string(label.get("name").unwrap_or(label)))
+                        .collect();
+                    match labels {
+                        Some(l) => l.join(&self.label_separator.to_string()),
+                        None => {
+                            return Err(format!(
+                                "The labels on {} must be strings or objects with a name",
+                                row
+                            ))
+                        }
+                    }
+                }
+                // The issues to link to can be given as a list too
+                None if self.link_type_of_key(key).is_some() && value.is_array() => {
+                    let titles: Option<Vec<String>> = value
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(value_to_string)
+                        .collect();
+                    match titles {
+                        Some(t) => t.join(&LINK_SEPARATOR.to_string()),
+                        None => return Err(format!("The links on {} must be strings", row)),
+                    }
+                }
+                // Nested values can be used in the description template
+                None if self.description_template.is_some() => continue,
+                // Lists and objects are combined into the description as compact json
+                None if self.combine_remaining
+                    && !keys_match(key, our_title_name, self.case_sensitive_keys) =>
+                {
+                    value.to_string()
+                }
+                None if self.reads_key(key) => {
+                    return Err(format!(
+                        "The value of '{}' on {} is not a string, number or boolean",
+                        key, row
+                    ))
+                }
+                // Exports often have lists and objects we do not need, e.g. the author
+                None => continue,
             };
             // Get title
-            if key.to_lowercase() == our_title_name {
-                title = val;
+            if keys_match(key, our_title_name, self.case_sensitive_keys) {
+                title = Some(val);
+            } else if key_matches(key, &self.labels_key, self.case_sensitive_keys) {
+                // Get labels, split once we know the title
+                if !val.trim().is_empty() {
+                    labels = Some(val);
+                }
+            } else if key_matches(key, &self.due_date_key, self.case_sensitive_keys) {
+                // Get due date, validated once we know the title
+                due_date = Some(val);
+            } else if key_matches(key, &self.confidential_key, self.case_sensitive_keys) {
+                // Get confidential
+                confidential = is_truthy(&val);
+            } else if key_matches(key, &self.weight_key, self.case_sensitive_keys) {
+                // Get weight, validated once we know the title
+                weight = Some(val);
+            } else if key_matches(key, &self.estimate_key, self.case_sensitive_keys) {
+                // Get estimate, validated once we know the title
+                estimate = Some(val);
+            } else if key_matches(key, &self.issue_type_key, self.case_sensitive_keys) {
+                // Get issue type, validated once we know the title
+                issue_type = Some(val);
+            } else if key_matches(key, &self.assignee_key, self.case_sensitive_keys) {
+                // Get assignee
+                assignee = parse_assignee(&val);
+            } else if key_matches(key, &self.created_at_key, self.case_sensitive_keys) {
+                // Get created at, validated once we know the title
+                created_at = Some(val);
+            } else if key_matches(key, &self.project_key, self.case_sensitive_keys) {
+                // Get project
+                project = parse_project(&val);
+            } else if key_matches(key, &self.comment_key, self.case_sensitive_keys) {
+                // Get comment
+                if !val.trim().is_empty() {
+                    comment = Some(val);
+                }
+            } else if let Some(link_type) = self.link_type_of_key(key) {
+                // Get the titles of the issues to link to
+                links.extend(self.parse_links(&val, link_type));
             } else {
                 // Get description
                 if self.combine_remaining {
                     // Combine remaining columns into description
-                    description_string.push(format!("{}: {}\n\n", key.trim(), val));
+                    if self.skip_empty_fields && (value.is_null() || val.trim().is_empty()) {
+                        continue;
+                    }
+                    combined_fields.push((key.trim().to_string(), val));
                 } else {
                     // Get description from key name if it is set
-                    if self.description_key.is_some() {
-                        let our_description_name =
-                            self.description_key.as_ref().unwrap().to_lowercase();
-                        if key.to_lowercase() == our_description_name {
+                    if let Some(description_key) = self.description_key.as_ref() {
+                        // An issue without a description has null in some exports
+                        if keys_match(key, description_key, self.case_sensitive_keys)
+                            && !value.is_null()
+                        {
                             description_string = vec![val];
                         }
                     }
                 }
             }
         }
+        if !combined_fields.is_empty() {
+            description_string = vec![combine_fields(&combined_fields, &self.combine_format)];
+        }
+        if let Some(path) = title_path {
+            title = Some(path_to_string(data, path, row, self.case_sensitive_keys)?);
+        }
+        if let Some(path) = description_path {
+            description_string = vec![path_to_string(data, path, row, self.case_sensitive_keys)?];
+        }
+        if let Some(description_keys) = self.description_keys.as_ref() {
+            // Combine the chosen keys into description, in the given order
+            let fields: Vec<(String, String)> = description_keys
+                .iter()
+                .filter_map(|key| {
+                    lookup_value(data, key, row, self.case_sensitive_keys)
+                        .map(|v| (key.trim().to_string(), v))
+                })
+                .collect();
+            description_string = match fields.is_empty() {
+                true => Vec::new(),
+                false => vec![combine_fields(&fields, &self.combine_format)],
+            };
+        } else if self.description_from_file
+            && !self.combine_remaining
+            && self.description_template.is_none()
+        {
+            // Only the single description key holds the path of a file
+            if let Some(path) = description_string.first() {
+                description_string = self.read_description_file(path, row)?.into_iter().collect();
+            }
+        }
+        if let (Some(template), false) =
+            (self.description_template.as_ref(), self.combine_remaining)
+        {
+            // Fill the template with the values of this object, nested values by their path
+            let lookup = |name: &str| lookup_value(data, name, row, self.case_sensitive_keys);
+            description_string = vec![expand_template(
+                template,
+                lookup,
+                self.strict_template,
+                row,
+            )?];
+        }
+        // Drop objects outside of --filter-since and --filter-until
+        if let Some(filter_key) = self.filter_key.as_ref() {
+            let date =
+                lookup_value(data, filter_key, row, self.case_sensitive_keys).unwrap_or_default();
+            if !self.in_date_range(&date, row)? {
+                debug!("Skipping {}, its date is outside of the filter range", row);
+                return Ok(None);
+            }
+        }
         // Check if we have a title
-        if title.is_empty() {
-            return Err(String::from("Could not find title"));
+        let title = match title {
+            Some(t) => t,
+            None => return Err(String::from("Could not find title")),
+        };
+        if title.trim().is_empty() {
+            match self.skip_empty_titles {
+                true => {
+                    debug!("Skipping {}, it has an empty title", row);
+                    return Ok(None);
+                }
+                false => return Err(empty_title_error(row)),
+            }
         }
-        Ok(IssueFromFile {
-            title: title,
-            description: match description_string.is_empty() {
+        let labels = match labels {
+            Some(l) => Some(normalize_labels(&l, self.label_separator, row)?),
+            None => None,
+        };
+        let due_date = match due_date {
+            Some(d) => validate_due_date(&d, row)?,
+            None => None,
+        };
+        let weight = match weight {
+            Some(w) => parse_weight(&w, row)?,
+            None => None,
+        };
+        let estimate = match estimate {
+            Some(e) => parse_estimate(&e, row)?,
+            None => None,
+        };
+        let issue_type = match issue_type {
+            Some(t) => parse_issue_type(&t, row)?,
+            None => None,
+        };
+        let created_at = match created_at {
+            Some(c) => parse_created_at(&c, row)?,
+            None => None,
+        };
+        Ok(Some(IssueFromFile {
+            title: match self.prepend_title.as_ref() {
+                Some(p) => format!("{} {}", p, title),
+                None => title,
+            },
+            description: self.decorate_description(match description_string.is_empty() {
                 true => None,
                 false => Some(description_string.join("")),
-            },
+            }),
+            labels,
+            due_date,
+            confidential,
+            weight,
+            estimate,
+            issue_type,
+            assignee,
+            created_at,
+            project,
+            comment,
+            links,
+        }))
+    }
+    /// Is the key of a json or yaml object read into the issue?
+    fn reads_key(&self, key: &str) -> bool {
+        [
+            &self.title_key,
+            &self.description_key,
+            &self.labels_key,
+            &self.due_date_key,
+            &self.confidential_key,
+            &self.weight_key,
+            &self.estimate_key,
+            &self.issue_type_key,
+            &self.assignee_key,
+            &self.created_at_key,
+            &self.project_key,
+            &self.comment_key,
+            &self.filter_key,
+        ]
+        .into_iter()
+        .any(|wanted| key_matches(key, wanted, self.case_sensitive_keys))
+            || self.link_type_of_key(key).is_some()
+    }
+    /// The link type of every link column, with its index
+    fn link_columns(&self) -> [(&'static str, Option<usize>); 3] {
+        [
+            ("relates_to", self.relates_to_column_index),
+            ("blocks", self.blocks_column_index),
+            ("is_blocked_by", self.blocked_by_column_index),
+        ]
+    }
+    /// The link type of a key of a json or yaml object, None if it is not a link key
+    fn link_type_of_key(&self, key: &str) -> Option<&'static str> {
+        [
+            ("relates_to", &self.relates_to_key),
+            ("blocks", &self.blocks_key),
+            ("is_blocked_by", &self.blocked_by_key),
+        ]
+        .into_iter()
+        .find(|(_, wanted)| key_matches(key, wanted, self.case_sensitive_keys))
+        .map(|(link_type, _)| link_type)
+    }
+    /// Split the titles of the issues to link to. --prepend-title is added to them,
+    /// so they match the titles of the created issues.
+    fn parse_links(&self, titles: &str, link_type: &'static str) -> Vec<(&'static str, String)> {
+        titles
+            .split(LINK_SEPARATOR)
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(|t| match self.prepend_title.as_ref() {
+                Some(p) => (link_type, format!("{} {}", p, t)),
+                None => (link_type, t.to_string()),
+            })
+            .collect()
+    }
+    /// Is the date of a record within --filter-since and --filter-until?
+    /// Dates that can not be parsed are an error with --strict, otherwise the record is skipped.
+    fn in_date_range(&self, date: &str, row: &str) -> Result<bool, String> {
+        let date = match parse_date(date) {
+            Some(d) => d,
+            None if self.strict => {
+                return Err(format!(
+                    "Invalid date '{}' on {}, expected YYYY-MM-DD",
+                    date.trim(),
+                    row
+                ))
+            }
+            None => {
+                warn!("Skipping {}, could not parse date '{}'", row, date.trim());
+                return Ok(false);
+            }
+        };
+        Ok(self.filter_since.is_none_or(|since| date >= since)
+            && self.filter_until.is_none_or(|until| date <= until))
+    }
+    /// Add --prepend-description and --append-description to the description.
+    /// Issues without a description get one from just the prefix and suffix.
+    fn decorate_description(&self, description: Option<String>) -> Option<String> {
+        // Title only issues, e.g. the paragraphs of a text file or the body of an org headline
+        if self.no_description {
+            return None;
+        }
+        let parts: Vec<&str> = [
+            self.prepend_description.as_deref(),
+            description.as_deref(),
+            self.append_description.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|p| !p.is_empty())
+        .collect();
+        match parts.is_empty() {
+            true => None,
+            false => Some(parts.join("\n\n")),
+        }
+    }
+}
+
+/// Combine keys and their values into a description, in one of COMBINE_FORMATS:
+/// kv is <key>: <value> paragraphs, bullets a markdown list and table a markdown table
+fn combine_fields(fields: &[(String, String)], format: &str) -> String {
+    match format {
+        "bullets" => fields
+            .iter()
+            .map(|(key, value)| format!("- **{}**: {}\n", key, value))
+            .collect(),
+        "table" if !fields.is_empty() => {
+            // Pipes would end the cell and newlines the row
+            let cell = |s: &str| {
+                s.replace('|', "\\|")
+                    .replace("\r\n", "<br>")
+                    .replace('\n', "<br>")
+            };
+            let rows: String = fields
+                .iter()
+                .map(|(key, value)| format!("| {} | {} |\n", cell(key), cell(value)))
+                .collect();
+            format!("| Field | Value |\n| --- | --- |\n{}", rows)
+        }
+        "table" => String::new(),
+        // Double newlines because GitLab uses them to separate paragraphs
+        _ => fields
+            .iter()
+            .map(|(key, value)| format!("{}: {}\n\n", key, value))
+            .collect(),
+    }
+}
+
+/// Turn a scalar json value into a string, None for arrays and objects
+fn value_to_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Null => Some(String::from("null")),
+        _ => None,
+    }
+}
+
+/// Get the value of a key, or of a dot separated path into nested objects, as a string
+fn lookup_value(
+    data: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    row: &str,
+    case_sensitive: bool,
+) -> Option<String> {
+    match is_path(data, key, case_sensitive) {
+        true => path_to_string(data, key, row, case_sensitive).ok(),
+        false => data
+            .iter()
+            .find(|(k, _)| keys_match(k, key, case_sensitive))
+            .and_then(|(_, v)| value_to_string(v)),
+    }
+}
+
+/// Is the key a dot separated path into nested objects, e.g. meta.summary?
+/// A top level key that contains dots is used as is.
+fn is_path(
+    data: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    case_sensitive: bool,
+) -> bool {
+    key.contains('.') && !data.keys().any(|k| keys_match(k, key, case_sensitive))
+}
+
+/// Is the key the first segment of the path?
+fn is_path_root(key: &str, path: &str, case_sensitive: bool) -> bool {
+    keys_match(
+        path.split('.').next().unwrap_or_default(),
+        key,
+        case_sensitive,
+    )
+}
+
+/// Follow a dot separated path through nested objects and return the value as a string.
+/// Every segment is matched like top level keys.
+fn path_to_string(
+    data: &serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    row: &str,
+    case_sensitive: bool,
+) -> Result<String, String> {
+    let mut object = data;
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        let value = match object
+            .iter()
+            .find(|(k, _)| keys_match(k, segment, case_sensitive))
+        {
+            Some((_, v)) => v,
+            None => {
+                return Err(format!(
+                    "Could not find '{}' on {}, '{}' is missing",
+                    path, row, segment
+                ))
+            }
+        };
+        if segments.peek().is_none() {
+            return match value_to_string(value) {
+                Some(v) => Ok(v),
+                None => Err(format!(
+                    "Could not read '{}' on {}, it is not a string, number or boolean",
+                    path, row
+                )),
+            };
+        }
+        object = match value.as_object() {
+            Some(o) => o,
+            None => {
+                return Err(format!(
+                    "Could not find '{}' on {}, '{}' is not an object",
+                    path, row, segment
+                ))
+            }
+        };
+    }
+    Err(format!("Could not find '{}' on {}", path, row))
+}
+
+/// Follow a dot separated path, e.g. data.issues, to the array or object with the issues.
+/// Numbers in the path are indexes into arrays
+fn find_json_root<'a>(
+    data: &'a serde_json::Value,
+    root: &str,
+    case_sensitive: bool,
+) -> Result<&'a serde_json::Value, String> {
+    let mut value = data;
+    for segment in root.split('.') {
+        let next = match value {
+            serde_json::Value::Object(object) => object
+                .iter()
+                .find(|(k, _)| keys_match(k, segment, case_sensitive))
+                .map(|(_, v)| v),
+            serde_json::Value::Array(array) => {
+                segment.parse::<usize>().ok().and_then(|i| array.get(i))
+            }
+            _ => None,
+        };
+        value = match next {
+            Some(v) => v,
+            None => {
+                return Err(format!(
+                    "Could not find the json root '{}', '{}' is missing in {}",
+                    root,
+                    segment,
+                    json_type_name(value)
+                ))
+            }
+        };
+    }
+    match value.is_array() || value.is_object() {
+        true => Ok(value),
+        false => Err(format!(
+            "The json root '{}' must be an array or an object, found {}",
+            root,
+            json_type_name(value)
+        )),
+    }
+}
+
+fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// Replace the {field} placeholders of a description template with the values lookup returns.
+/// Placeholders lookup knows nothing about are an error if strict is set, otherwise they are kept as is.
+fn expand_template(
+    template: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    strict: bool,
+    row: &str,
+) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        expanded.push_str(&rest[..start]);
+        let placeholder = &rest[start..=end];
+        match lookup(rest[start + 1..end].trim()) {
+            Some(value) => expanded.push_str(&value),
+            None if strict => {
+                return Err(format!(
+                    "Unknown placeholder {} in description template on {}",
+                    placeholder, row
+                ))
+            }
+            None => expanded.push_str(placeholder),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Replace ${VAR} with the value lookup returns for the environment variable VAR.
+/// Variables lookup knows nothing about are an error, unless allow_undefined is set,
+/// then they are replaced with nothing.
+pub fn expand_env(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    allow_undefined: bool,
+) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        expanded.push_str(&rest[..start]);
+        let name = &rest[start + 2..end];
+        match lookup(name) {
+            Some(value) => expanded.push_str(&value),
+            None if allow_undefined => (),
+            None => {
+                return Err(format!(
+                    "The environment variable {} is not set, use --allow-undefined-env to replace it with nothing",
+                    name
+                ))
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn empty_title_error(row: &str) -> String {
+    format!(
+        "The title of {} is empty, use --skip-empty-titles to skip issues without a title",
+        row
+    )
+}
+
+/// Find the index of the column with the given name in the headers, matching any case
+fn find_column_index(
+    headers: &csv::StringRecord,
+    name: &str,
+    case_sensitive: bool,
+) -> Result<usize, String> {
+    debug!(
+        "User specified column: '{}', trying to find column index...",
+        name
+    );
+    if !case_sensitive {
+        warn_ambiguous_keys(headers.iter(), name, "the header");
+    }
+    match headers
+        .iter()
+        .position(|x| keys_match(x, name, case_sensitive))
+    {
+        Some(i) => {
+            debug!("Found column '{}' at index {}", name, i);
+            Ok(i)
+        }
+        None => Err(format!("Could not find column with name '{}'", name)),
+    }
+}
+
+/// Check if a json/yaml key matches the key name the user asked for
+fn key_matches(key: &str, wanted: &Option<String>, case_sensitive: bool) -> bool {
+    wanted
+        .as_ref()
+        .is_some_and(|w| keys_match(key, w, case_sensitive))
+}
+
+/// Compare a header or key of the file with a name the user gave, ignoring case
+/// unless --case-sensitive-keys is set
+fn keys_match(key: &str, name: &str, case_sensitive: bool) -> bool {
+    match case_sensitive {
+        true => key == name,
+        false => key.to_lowercase() == name.to_lowercase(),
+    }
+}
+
+/// Warn if more than one header or key matches a name when ignoring case, e.g. Title and title
+fn warn_ambiguous_keys<'a>(keys: impl Iterator<Item = &'a str>, name: &str, row: &str) {
+    let matching: Vec<&str> = keys.filter(|k| keys_match(k, name, false)).collect();
+    if matching.len() > 1 {
+        warn!(
+            "{:?} on {} all match '{}', use --case-sensitive-keys to match exactly",
+            matching, row, name
+        );
+    }
+}
+
+/// Check if a value from the file means "yes", e.g. true, yes or 1
+fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "true" | "yes" | "1")
+}
+
+/// Parse the type of an issue, which must be one of ISSUE_TYPES.
+/// Empty values mean the issue gets the default type.
+fn parse_issue_type(issue_type: &str, row: &str) -> Result<Option<String>, String> {
+    let issue_type = issue_type.trim().to_lowercase();
+    if issue_type.is_empty() {
+        return Ok(None);
+    }
+    match ISSUE_TYPES.contains(&issue_type.as_str()) {
+        true => Ok(Some(issue_type)),
+        false => Err(format!(
+            "Invalid issue type '{}' on {}, expected one of {:?}",
+            issue_type, row, ISSUE_TYPES
+        )),
+    }
+}
+
+/// Drop the first lines of the contents, an error if there are not that many lines
+fn skip_lines(contents: &str, lines: usize) -> Result<&str, String> {
+    let mut rest = contents;
+    for _ in 0..lines {
+        match rest.find('\n') {
+            Some(i) => rest = &rest[i + 1..],
+            None => {
+                return Err(format!(
+                    "Could not skip {} rows, the file only has {} lines",
+                    lines,
+                    contents.lines().count()
+                ))
+            }
+        }
+    }
+    Ok(rest)
+}
+
+/// Pick the most frequent delimiter candidate in the line, defaulting to comma
+fn detect_delimiter(line: &str) -> char {
+    let mut best = (',', 0);
+    for candidate in DELIMITER_CANDIDATES {
+        let count = line.matches(candidate).count();
+        if count > best.1 {
+            best = (candidate, count);
+        }
+    }
+    best.0
+}
+
+/// Turn a spreadsheet cell into the string we would get from a csv file.
+/// Dates are formatted as YYYY-MM-DD, which is what GitLab expects for due dates.
+fn cell_to_string(cell: &calamine::Data) -> String {
+    match cell {
+        calamine::Data::DateTime(_) | calamine::Data::DateTimeIso(_) => match cell.as_date() {
+            Some(date) => date.format("%Y-%m-%d").to_string(),
+            None => cell.to_string(),
+        },
+        _ => cell.to_string(),
+    }
+}
+
+/// Remove issues with the same title and description as an earlier issue,
+/// or with the same title only if by_title is set. Returns how many were removed
+pub fn dedup_issues(issues: &mut Vec<IssueFromFile>, by_title: bool) -> usize {
+    let count = issues.len();
+    let mut seen: std::collections::HashSet<(String, Option<String>)> =
+        std::collections::HashSet::new();
+    issues.retain(|issue| {
+        let description = match by_title {
+            true => None,
+            false => issue.description.clone(),
+        };
+        seen.insert((issue.title.clone(), description))
+    });
+    count - issues.len()
+}
+
+/// Split the tags, e.g. :bug:backend:, off an org-mode headline.
+/// Returns the title without a priority cookie like [#A] and the tags joined with separator
+fn split_org_tags(headline: &str, separator: char) -> (String, Option<String>) {
+    let mut title = headline.trim();
+    let mut labels = None;
+    if let Some((rest, tags)) = title.rsplit_once(char::is_whitespace) {
+        if tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':') {
+            title = rest.trim_end();
+            labels = Some(
+                tags.trim_matches(':')
+                    .split(':')
+                    .collect::<Vec<&str>>()
+                    .join(&separator.to_string()),
+            );
+        }
+    }
+    if let Some(rest) = title.strip_prefix("[#") {
+        if let Some((_, rest)) = rest.split_once("] ") {
+            title = rest.trim_start();
+        }
+    }
+    (title.to_string(), labels)
+}
+
+/// Split the body of a markdown task into the keys of its front-matter, the YAML block
+/// between --- lines at the top, and the text after it
+fn split_front_matter(
+    body: &[&str],
+    row: &str,
+) -> Result<(serde_json::Map<String, serde_json::Value>, String), String> {
+    let start = body.iter().position(|l| !l.is_empty());
+    let start = match start {
+        Some(s) if body[s] == "---" => s,
+        _ => return Ok((serde_json::Map::new(), body.join("\n"))),
+    };
+    let end = match body[start + 1..].iter().position(|l| *l == "---") {
+        Some(e) => start + 1 + e,
+        None => {
+            return Err(format!(
+                "The front-matter on {} is not closed with ---",
+                row
+            ))
+        }
+    };
+    let yaml = body[start + 1..end].join("\n");
+    let front_matter = match serde_yaml::from_str::<serde_json::Value>(&yaml) {
+        Ok(serde_json::Value::Object(map)) => map,
+        // An empty block has no keys
+        Ok(serde_json::Value::Null) => serde_json::Map::new(),
+        Ok(value) => {
+            return Err(format!(
+                "The front-matter on {} is {}, expected keys like labels: bug",
+                row,
+                json_type_name(&value)
+            ))
+        }
+        Err(e) => {
+            return Err(format!(
+                "Could not parse the front-matter on {}: {}",
+                row, e
+            ))
+        }
+    };
+    Ok((front_matter, body[end + 1..].join("\n")))
+}
+
+/// Validate that a due date is in the YYYY-MM-DD format GitLab expects.
+/// Empty values mean the issue has no due date.
+fn validate_due_date(due_date: &str, row: &str) -> Result<Option<String>, String> {
+    let due_date = due_date.trim();
+    if due_date.is_empty() {
+        return Ok(None);
+    }
+    match chrono::NaiveDate::parse_from_str(due_date, "%Y-%m-%d") {
+        Ok(_) => Ok(Some(due_date.to_string())),
+        Err(_) => Err(format!(
+            "Invalid due date '{}' on {}, expected YYYY-MM-DD",
+            due_date, row
+        )),
+    }
+}
+
+/// Parse a date like 2024-01-31, the date of a timestamp like 2024-01-31T12:00:00Z is used
+pub fn parse_date(date: &str) -> Option<chrono::NaiveDate> {
+    let date = date.trim();
+    if let Ok(d) = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Some(d);
+    }
+    if let Ok(d) = chrono::DateTime::parse_from_rfc3339(date) {
+        return Some(d.date_naive());
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|f| chrono::NaiveDateTime::parse_from_str(date, f).ok())
+        .map(|d| d.date())
+}
+
+/// Split a list of labels on the label separator and join them with commas,
+/// the way GitLab expects them. GitLab does not allow commas in label names.
+pub fn normalize_labels(labels: &str, separator: char, row: &str) -> Result<String, String> {
+    if separator == ',' {
+        return Ok(labels.to_string());
+    }
+    let labels: Vec<&str> = labels.split(separator).map(|l| l.trim()).collect();
+    if let Some(label) = labels.iter().find(|l| l.contains(',')) {
+        return Err(format!(
+            "Label '{}' on {} contains a comma, GitLab does not allow commas in label names",
+            label, row
+        ));
+    }
+    Ok(labels.join(","))
+}
+
+/// Validate the creation time of an issue, a date or an ISO 8601 timestamp.
+/// Empty values mean the issue is created now.
+pub fn parse_created_at(created_at: &str, row: &str) -> Result<Option<String>, String> {
+    let created_at = created_at.trim();
+    if created_at.is_empty() {
+        return Ok(None);
+    }
+    match parse_date(created_at) {
+        Some(_) => Ok(Some(created_at.to_string())),
+        None => Err(format!(
+            "Invalid created at '{}' on {}, expected a date or timestamp like 2024-01-31T12:00:00Z",
+            created_at, row
+        )),
+    }
+}
+
+/// Get the project of an issue, empty values mean the issue goes to the project of all issues
+fn parse_project(project: &str) -> Option<String> {
+    match project.trim() {
+        "" => None,
+        p => Some(p.to_string()),
+    }
+}
+/// Get the username of an assignee, with or without a leading @.
+/// Empty values mean the issue has no assignee of its own.
+fn parse_assignee(assignee: &str) -> Option<String> {
+    let assignee = assignee.trim().trim_start_matches('@');
+    match assignee.is_empty() {
+        true => None,
+        false => Some(assignee.to_string()),
+    }
+}
+
+/// Parse the weight of an issue, which must be a non-negative number.
+/// Empty values mean the issue has no weight.
+fn parse_weight(weight: &str, row: &str) -> Result<Option<u32>, String> {
+    let weight = weight.trim();
+    if weight.is_empty() {
+        return Ok(None);
+    }
+    match weight.parse::<u32>() {
+        Ok(w) => Ok(Some(w)),
+        Err(_) => Err(format!(
+            "Invalid weight '{}' on {}, expected a non-negative number",
+            weight, row
+        )),
+    }
+}
+
+/// Parse the time estimate of an issue, e.g. "2h" or "1d 4h".
+/// Every part must be a number followed by one of the units GitLab accepts (w, d, h, m, s).
+/// Empty values mean the issue has no estimate.
+fn parse_estimate(estimate: &str, row: &str) -> Result<Option<String>, String> {
+    let estimate = estimate.trim();
+    if estimate.is_empty() {
+        return Ok(None);
+    }
+    let valid = estimate.split_whitespace().all(|part| {
+        match part.strip_suffix(['w', 'd', 'h', 'm', 's']) {
+            Some(number) => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+            None => false,
+        }
+    });
+    match valid {
+        true => Ok(Some(estimate.to_string())),
+        false => Err(format!(
+            "Invalid estimate '{}' on {}, expected a duration like 2h or 1d 4h",
+            estimate, row
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A parser with the defaults of the command line options, reading title and description by key
+    fn parser(file: &str) -> FileParser {
+        FileParser::new(ParserOptions {
+            file: PathBuf::from(file),
+            separator: Some(','),
+            title_key: Some(String::from("title")),
+            description_key: Some(String::from("description")),
+            quote_char: '"',
+            label_separator: ',',
+            combine_format: String::from("kv"),
+            ..Default::default()
         })
     }
+
+    fn titles(issues: &[IssueFromFile]) -> Vec<&str> {
+        issues.iter().map(|i| i.title.as_str()).collect()
+    }
+
+    #[test]
+    fn csv_with_header_by_key() {
+        let csv = "title,description\ntask1,first\ntask2,second\n";
+        let issues = parser("issues.csv")
+            .parse_csv_reader(csv.as_bytes())
+            .unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        assert_eq!(issues[1].description.as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn csv_keys_match_any_case() {
+        let csv = "Title,DESCRIPTION\ntask1,first\n";
+        let issues = parser("issues.csv")
+            .parse_csv_reader(csv.as_bytes())
+            .unwrap();
+        assert_eq!(titles(&issues), ["task1"]);
+        assert_eq!(issues[0].description.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn csv_without_header_by_index() {
+        let mut parser = parser("issues.csv");
+        parser.no_header = true;
+        parser.title_key = None;
+        parser.title_column_index = Some(1);
+        parser.description_key = None;
+        parser.description_column_index = Some(0);
+        let csv = "first,task1\nsecond,task2\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        assert_eq!(issues[0].description.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn csv_case_sensitive_keys() {
+        let mut parser = parser("issues.csv");
+        parser.case_sensitive_keys = true;
+        let csv = "Title,title,description\nwrong,task1,first\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(titles(&issues), ["task1"]);
+    }
+
+    #[test]
+    fn csv_with_header_by_index() {
+        let mut parser = parser("issues.csv");
+        parser.title_key = None;
+        parser.title_column_index = Some(1);
+        parser.description_key = None;
+        let csv = "id,summary\n1,task1\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(titles(&issues), ["task1"]);
+        assert_eq!(issues[0].description, None);
+    }
+
+    #[test]
+    fn csv_combine_remaining() {
+        let mut parser = parser("issues.csv");
+        parser.combine_remaining = true;
+        parser.labels_key = Some(String::from("labels"));
+        let csv = "title,owner,labels,notes\ntask1,alice,bug,urgent\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        // The title and the labels are not part of the description
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("owner: alice\n\nnotes: urgent\n\n")
+        );
+        assert_eq!(issues[0].labels.as_deref(), Some("bug"));
+    }
+
+    #[test]
+    fn combine_remaining_skips_empty_fields() {
+        let mut parser = parser("issues.csv");
+        parser.combine_remaining = true;
+        parser.skip_empty_fields = true;
+        let csv = "title,owner,notes\ntask1, ,urgent\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(issues[0].description.as_deref(), Some("notes: urgent\n\n"));
+        let json = r#"[{"title": "task1", "owner": null, "notes": "urgent", "due": ""}]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(issues[0].description.as_deref(), Some("notes: urgent\n\n"));
+    }
+
+    #[test]
+    fn combine_remaining_as_bullets_or_table() {
+        let mut parser = parser("issues.csv");
+        parser.combine_remaining = true;
+        parser.combine_format = String::from("bullets");
+        let csv = "title,owner,notes\ntask1,alice,\"a|b\nc\"\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("- **owner**: alice\n- **notes**: a|b\nc\n")
+        );
+        parser.combine_format = String::from("table");
+        let json = r#"[{"title": "task1", "owner": "alice", "notes": "a|b\nc"}]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("| Field | Value |\n| --- | --- |\n| notes | a\\|b<br>c |\n| owner | alice |\n")
+        );
+    }
+
+    #[test]
+    fn csv_description_keys_in_order() {
+        let mut parser = parser("issues.csv");
+        parser.description_keys = Some(vec![String::from("expected"), String::from("steps")]);
+        let csv = "title,steps,notes,expected\ntask1,click,ignored,works\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("expected: works\n\nsteps: click\n\n")
+        );
+    }
+
+    #[test]
+    fn csv_project_by_key() {
+        let mut parser = parser("issues.csv");
+        parser.project_key = Some(String::from("repo"));
+        let csv = "title,description,repo\ntask1,first,group/backend\ntask2,second, \n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(issues[0].project.as_deref(), Some("group/backend"));
+        assert_eq!(issues[1].project, None);
+    }
+
+    #[test]
+    fn json_comment_by_key() {
+        let mut parser = parser("issues.json");
+        parser.comment_key = Some(String::from("note"));
+        parser.combine_remaining = true;
+        let json = r#"[{"title": "task1", "note": "see #4", "owner": "alice"}, {"title": "task2", "note": " "}]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(issues[0].comment.as_deref(), Some("see #4"));
+        assert_eq!(issues[0].description.as_deref(), Some("owner: alice\n\n"));
+        assert_eq!(issues[1].comment, None);
+    }
+
+    #[test]
+    fn csv_links_by_key() {
+        let mut parser = parser("issues.csv");
+        parser.blocks_key = Some(String::from("blocks"));
+        parser.relates_to_key = Some(String::from("related"));
+        parser.combine_remaining = true;
+        let csv = "title,blocks,related,notes\ntask1,task2; task3,,n\ntask2,,task1,\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(
+            issues[0].links,
+            [
+                ("blocks", String::from("task2")),
+                ("blocks", String::from("task3"))
+            ]
+        );
+        assert_eq!(issues[0].description.as_deref(), Some("notes: n\n\n"));
+        assert_eq!(issues[1].links, [("relates_to", String::from("task1"))]);
+    }
+
+    #[test]
+    fn json_links_as_list() {
+        let mut parser = parser("issues.json");
+        parser.blocked_by_key = Some(String::from("blocked_by"));
+        let json = r#"[{"title": "task1", "blocked_by": ["task2", "task3"]}]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(
+            issues[0].links,
+            [
+                ("is_blocked_by", String::from("task2")),
+                ("is_blocked_by", String::from("task3"))
+            ]
+        );
+    }
+
+    #[test]
+    fn json_github_export() {
+        let mut parser = parser("issues.json");
+        parser.description_key = Some(String::from("body"));
+        parser.labels_key = Some(String::from("labels"));
+        let json = r#"[
+            {"title": "task1", "body": "first", "user": {"login": "alice"},
+             "labels": [{"id": 1, "name": "bug"}, {"id": 2, "name": "help wanted"}]},
+            {"title": "task2", "body": null, "labels": [], "assignees": []}
+        ]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        assert_eq!(issues[0].description.as_deref(), Some("first"));
+        assert_eq!(issues[0].labels.as_deref(), Some("bug,help wanted"));
+        assert_eq!(issues[1].description, None);
+        assert_eq!(issues[1].labels, None);
+    }
+
+    #[test]
+    fn csv_multibyte_separator_is_an_error() {
+        let mut parser = parser("issues.csv");
+        parser.separator = Some('，');
+        let csv = "title，description\ntask1，first\n";
+        assert!(parser.parse_csv_reader(csv.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn csv_assignee_by_key() {
+        let mut parser = parser("issues.csv");
+        parser.assignee_key = Some(String::from("owner"));
+        let csv = "title,description,owner\ntask1,first,@alice\ntask2,second,\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(issues[0].assignee.as_deref(), Some("alice"));
+        assert_eq!(issues[1].assignee, None);
+    }
+
+    #[test]
+    fn csv_missing_title_column() {
+        let mut parser = parser("issues.csv");
+        parser.title_key = Some(String::from("summary"));
+        let csv = "title,description\ntask1,first\n";
+        let error = parser.parse_csv_reader(csv.as_bytes()).err().unwrap();
+        assert_eq!(error, "Could not find column with name 'summary'");
+    }
+
+    #[test]
+    fn csv_title_index_out_of_bounds() {
+        let mut parser = parser("issues.csv");
+        parser.title_key = None;
+        parser.title_column_index = Some(5);
+        parser.description_key = None;
+        let csv = "title,description\ntask1,first\n";
+        let error = parser.parse_csv_reader(csv.as_bytes()).err().unwrap();
+        assert_eq!(error, "title_column_index is out of bounds");
+    }
+
+    #[test]
+    fn csv_with_too_few_columns() {
+        let mut parser = parser("issues.csv");
+        parser.min_columns = Some(2);
+        let csv = "title;description\ntask1;first\n";
+        let error = parser.parse_csv_reader(csv.as_bytes()).err().unwrap();
+        assert_eq!(
+            error,
+            "The header has 1 columns, expected at least 2. Is the separator ',' right?"
+        );
+    }
+
+    #[test]
+    fn csv_without_separator() {
+        let mut parser = parser("issues.csv");
+        parser.separator = None;
+        let csv = "title,description\ntask1,first\n";
+        let error = parser.parse_csv_reader(csv.as_bytes()).err().unwrap();
+        assert_eq!(
+            error,
+            "No separator for the csv file, use --separator or --detect-delimiter"
+        );
+    }
+
+    #[test]
+    fn csv_file_that_can_not_be_read() {
+        let error = parser("does/not/exist.csv").get_issues().err().unwrap();
+        assert!(
+            error.starts_with("Could not read file does/not/exist.csv: "),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn csv_prepend_title() {
+        let mut parser = parser("issues.csv");
+        parser.prepend_title = Some(String::from("TODO:"));
+        let csv = "title,description\ntask1,first\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(titles(&issues), ["TODO: task1"]);
+    }
+
+    #[test]
+    fn csv_quoted_multiline_fields() {
+        let mut parser = parser(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/example_multiline.csv"
+        ));
+        parser.separator = Some(';');
+        let issues = parser.get_issues().unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2", "task3"]);
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("A description\nthat spans several lines; and contains the separator")
+        );
+        assert_eq!(
+            issues[1].description.as_deref(),
+            Some("Quotes inside a quoted field are doubled: \"like this\"")
+        );
+    }
+
+    #[test]
+    fn csv_skip_rows_before_header() {
+        let csv = "Issue export\r\nExported at 2024-01-31\r\ntitle,description\r\ntask1,first\r\n";
+        let issues = parser("issues.csv")
+            .parse_csv_reader(skip_lines(csv, 2).unwrap().as_bytes())
+            .unwrap();
+        assert_eq!(titles(&issues), ["task1"]);
+        let mut parser = parser("issues.csv");
+        parser.no_header = true;
+        parser.title_column_index = Some(0);
+        parser.description_column_index = Some(1);
+        let issues = parser
+            .parse_csv_reader(skip_lines(csv, 3).unwrap().as_bytes())
+            .unwrap();
+        assert_eq!(titles(&issues), ["task1"]);
+        assert!(skip_lines(csv, 5).is_err());
+    }
+
+    #[test]
+    fn csv_escape_and_comment_chars() {
+        let mut parser = parser("issues.csv");
+        parser.escape_char = Some('\\');
+        parser.comment_char = Some('#');
+        let csv = "title,description\n# exported by the tracker\ntask1,\"say \\\"hi\\\"\"\n";
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(titles(&issues), ["task1"]);
+        assert_eq!(issues[0].description.as_deref(), Some("say \"hi\""));
+    }
+
+    #[test]
+    fn csv_flexible_column_count() {
+        let csv = "title,description,labels\ntask1,first\ntask2,second,bug,extra\n";
+        assert!(parser("issues.csv")
+            .parse_csv_reader(csv.as_bytes())
+            .is_err());
+        let mut parser = parser("issues.csv");
+        parser.flexible = true;
+        parser.labels_key = Some(String::from("labels"));
+        let issues = parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        assert_eq!(issues[0].labels, None);
+        assert_eq!(issues[1].labels.as_deref(), Some("bug"));
+    }
+
+    #[test]
+    fn csv_byte_order_mark_is_removed() {
+        let mut parser = parser(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/example_bom.csv"
+        ));
+        let issues = parser.get_issues().unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+    }
+
+    #[test]
+    fn csv_latin1_encoding() {
+        let mut parser = parser(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/example_latin1.csv"
+        ));
+        parser.encoding = encoding_rs::Encoding::for_label(b"latin1");
+        let issues = parser.get_issues().unwrap();
+        assert_eq!(titles(&issues), ["Café menu", "Résumé upload"]);
+        assert_eq!(issues[0].description.as_deref(), Some("Add crème brûlée"));
+    }
+
+    #[test]
+    fn org_todo_headlines() {
+        let parser = parser(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/example.org"));
+        let issues = parser.org_to_issues().unwrap();
+        assert_eq!(titles(&issues), ["task1", "task3"]);
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("Description of task1\n** Notes\nMore details")
+        );
+        assert_eq!(issues[0].labels.as_deref(), Some("bug,backend"));
+        assert_eq!(issues[1].labels, None);
+    }
+
+    #[test]
+    fn markdown_checklist_with_front_matter() {
+        let parser = parser(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/example.md"));
+        let issues = parser.markdown_to_issues().unwrap();
+        assert_eq!(titles(&issues), ["task1", "task3"]);
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("Description of task1\n\nMore details")
+        );
+        assert_eq!(issues[0].labels.as_deref(), Some("bug,backend"));
+        assert_eq!(issues[0].weight, Some(3));
+        assert_eq!(issues[0].estimate.as_deref(), Some("2h"));
+        assert_eq!(issues[0].assignee.as_deref(), Some("alice"));
+        assert_eq!(issues[1].description, None);
+        assert_eq!(issues[1].labels, None);
+    }
+
+    #[test]
+    fn markdown_front_matter_errors() {
+        let parser = parser("tasks.md");
+        let invalid_weight = "- [ ] task1\n  ---\n  weight: heavy\n  ---\n";
+        assert_eq!(
+            parser.parse_markdown_str(invalid_weight).err().unwrap(),
+            "Invalid weight 'heavy' on line 1, expected a non-negative number"
+        );
+        let not_closed = "- [ ] task1\n  ---\n  weight: 1\n- [ ] task2\n";
+        assert_eq!(
+            parser.parse_markdown_str(not_closed).err().unwrap(),
+            "The front-matter on line 1 is not closed with ---"
+        );
+    }
+
+    #[test]
+    fn dedup_by_title_and_description() {
+        let csv = "title,description\ntask1,first\ntask1,first\ntask1,second\n";
+        let mut issues = parser("issues.csv")
+            .parse_csv_reader(csv.as_bytes())
+            .unwrap();
+        assert_eq!(dedup_issues(&mut issues, false), 1);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(dedup_issues(&mut issues, true), 1);
+        assert_eq!(issues[0].description.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn text_paragraphs_without_description() {
+        let mut parser = parser("issues.txt");
+        parser.paragraph_mode = true;
+        parser.no_description = true;
+        let issues = parser.parse_text_str("task1\nfirst\n\ntask2\n");
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        assert_eq!(issues[0].description, None);
+    }
+
+    #[test]
+    fn text_one_issue_per_line() {
+        let mut parser = parser("issues.txt");
+        parser.prepend_title = Some(String::from("TODO:"));
+        let text = "# comment\ntask1\n\n  task2  \n";
+        let issues = parser.parse_text_str(text);
+        assert_eq!(titles(&issues), ["TODO: task1", "TODO: task2"]);
+        assert_eq!(issues[0].description, None);
+    }
+
+    #[test]
+    fn text_paragraph_mode() {
+        let mut parser = parser("notes.txt");
+        parser.paragraph_mode = true;
+        let text = "task1\nfirst line\n  second line\n\n\ntask2\n  \n\t\n";
+        let issues = parser.parse_text_str(text);
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("first line\n  second line")
+        );
+        assert_eq!(issues[1].description, None);
+    }
+
+    #[test]
+    fn env_variables_are_expanded() {
+        let lookup = |name: &str| match name {
+            "USER" => Some(String::from("alice")),
+            _ => None,
+        };
+        assert_eq!(
+            expand_env("Imported by ${USER} {notes}", lookup, false).unwrap(),
+            "Imported by alice {notes}"
+        );
+        assert!(expand_env("Team ${TEAM}", lookup, false).is_err());
+        assert_eq!(expand_env("Team ${TEAM}.", lookup, true).unwrap(), "Team .");
+    }
+
+    #[test]
+    fn json_array_by_key() {
+        let json = r#"[{"title": "task1", "description": "first"}, {"title": "task2"}]"#;
+        let issues = parser("issues.json").parse_json_str(json).unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        assert_eq!(issues[0].description.as_deref(), Some("first"));
+        assert_eq!(issues[1].description, None);
+    }
+
+    #[test]
+    fn json_root_path() {
+        let mut parser = parser("issues.json");
+        parser.json_root = Some(String::from("data.issues"));
+        let json = r#"{"data": {"issues": [{"title": "task1"}, {"title": "task2"}], "count": 2}}"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(titles(&issues), ["task1", "task2"]);
+        parser.json_root = Some(String::from("data.count"));
+        let error = parser.parse_json_str(json).err().unwrap();
+        assert_eq!(
+            error,
+            "The json root 'data.count' must be an array or an object, found a number"
+        );
+    }
+
+    #[test]
+    fn json_single_object() {
+        let json = r#"{"title": "task1", "description": "first"}"#;
+        let issues = parser("issues.json").parse_json_str(json).unwrap();
+        assert_eq!(titles(&issues), ["task1"]);
+    }
+
+    #[test]
+    fn json_combine_remaining() {
+        let mut parser = parser("issues.json");
+        parser.combine_remaining = true;
+        let json = r#"[{"title": "task1", "owner": "alice", "tags": ["a", "b"]}]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("owner: alice\n\ntags: [\"a\",\"b\"]\n\n")
+        );
+    }
+
+    #[test]
+    fn json_description_keys_in_order() {
+        let mut parser = parser("issues.json");
+        parser.description_keys = Some(vec![String::from("steps"), String::from("meta.owner")]);
+        let json = r#"[{"title": "task1", "meta": {"owner": "alice"}, "steps": "click"}]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("steps: click\n\nmeta.owner: alice\n\n")
+        );
+    }
+
+    #[test]
+    fn description_keys_are_not_read_from_file() {
+        let mut json_parser = parser("issues.json");
+        json_parser.description_from_file = true;
+        json_parser.description_keys =
+            Some(vec![String::from("description"), String::from("owner")]);
+        let json = r#"[{"title": "task1", "description": "docs/task1.md", "owner": "alice"}]"#;
+        let issues = json_parser.parse_json_str(json).unwrap();
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("description: docs/task1.md\n\nowner: alice\n\n")
+        );
+        let mut csv_parser = parser("issues.csv");
+        csv_parser.description_from_file = true;
+        csv_parser.description_column_indices = Some(vec![1, 2]);
+        let csv = "title,description,owner\ntask1,docs/task1.md,alice\n";
+        let issues = csv_parser.parse_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(
+            issues[0].description.as_deref(),
+            Some("description: docs/task1.md\n\nowner: alice\n\n")
+        );
+    }
+
+    #[test]
+    fn invalid_due_date_names_the_row() {
+        let csv = "title,description,due\ntask1,,2024-01-31\ntask2,,31.01.2024\n";
+        let mut parser = parser("issues.csv");
+        parser.due_date_key = Some(String::from("due"));
+        assert_eq!(
+            parser.parse_csv_reader(csv.as_bytes()).err().unwrap(),
+            "Invalid due date '31.01.2024' on line 3, expected YYYY-MM-DD"
+        );
+    }
+
+    #[test]
+    fn json_invalid_values_name_the_item() {
+        let mut parser = parser("issues.json");
+        parser.weight_key = Some(String::from("weight"));
+        let json = r#"[{"title": "task1", "weight": 1}, {"title": "task2", "weight": "heavy"}]"#;
+        assert_eq!(
+            parser.parse_json_str(json).err().unwrap(),
+            "Invalid weight 'heavy' on item 2, expected a non-negative number"
+        );
+    }
+
+    #[test]
+    fn json_missing_title() {
+        let json = r#"[{"summary": "task1"}]"#;
+        let error = parser("issues.json").parse_json_str(json).err().unwrap();
+        assert_eq!(error, "Could not find title");
+    }
+
+    #[test]
+    fn json_prepend_title() {
+        let mut parser = parser("issues.json");
+        parser.prepend_title = Some(String::from("TODO:"));
+        let json = r#"[{"title": "task1"}]"#;
+        let issues = parser.parse_json_str(json).unwrap();
+        assert_eq!(titles(&issues), ["TODO: task1"]);
+    }
 }
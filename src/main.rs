@@ -1,48 +1,203 @@
-use clap::Parser;
-use env_logger;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser};
+use indicatif::{ProgressBar, ProgressStyle};
 use log::{debug, error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
 
 // Local files
+mod configfile;
 mod gitlabapi;
 mod issuefile;
+mod report;
+mod statefile;
 
-const DEFAULT_GITLAB_URL: &'static str = "https://localhost";
+const DEFAULT_GITLAB_URL: &str = "https://localhost";
+// Exit code used when some of the issues could not be created
+const EXIT_PARTIAL_FAILURE: i32 = 2;
+// What --dedup-by can compare
+const DEDUP_BY: [&str; 2] = ["title", "title-description"];
+// Known export formats of --preset
+const PRESETS: [&str; 1] = ["github"];
+const COLORS: [&str; 3] = ["auto", "always", "never"];
+const LOG_FORMATS: [&str; 2] = ["text", "json"];
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  All issues were created (or skipped)
+  1  Invalid arguments or file, or GitLab could not be reached before creating issues
+  2  Some issues could not be created, the others were still created";
 
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about)]
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about, after_help = EXIT_CODES_HELP)]
 struct Args {
-    /// Path to the file to upload. Required.
-    #[arg(short, long, value_name = "FILE", required = true)]
+    /// Path to the file to upload. Required, unless --list-projects is given.
+    ///
+    /// Use "-" to read the issues from stdin, together with --format.
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        required_unless_present_any = ["list_projects", "health_check"]
+    )]
     file: Option<std::path::PathBuf>,
 
+    /// Format of the file, e.g. csv or json.
+    ///
+    /// Required when reading from stdin, otherwise the file extension is used.
+    #[arg(long)]
+    format: Option<String>,
+
+    /// Path to a toml or json file with default values for the other options.
+    ///
+    /// Keys are the long option names with underscores, e.g. title_key = "summary".
+    /// Options given on the command line override the values in the file.
+    #[arg(long, value_name = "CONFIG")]
+    config: Option<std::path::PathBuf>,
+
     /// Field separator to use when parsing a csv file.
     ///
-    /// Defaults to comma.
+    /// Defaults to comma, tsv files always use a tab.
+    /// The escape sequences \t (tab) and \0 (null) can be used, other characters
+    /// can be escaped with a backslash too, e.g. \|.
     /// Ignored if file is not a csv file.
     #[arg(short, long, default_value = ",")]
-    separator: Option<char>,
-    /// Does the csv file have a header row?
+    separator: Option<String>,
+    /// Detect the field separator of a csv file from its first line.
+    ///
+    /// The most frequent of comma, tab, semicolon and pipe is used, overriding --separator.
+    #[arg(long, default_value = "false")]
+    detect_delimiter: bool,
+    /// Quote character used when parsing a csv file.
+    ///
+    /// Fields wrapped in this character may contain the separator and newlines,
+    /// a quote character inside a quoted field is written twice, e.g. "say ""hi""".
+    /// Must be different from the separator.
+    #[arg(long, default_value = "\"")]
+    quote_char: char,
+    /// Escape character for quote characters inside quoted fields of a csv file, e.g. '\'
+    /// for "say \"hi\"".
+    ///
+    /// Doubled quote characters are still understood. By default there is no escape character.
+    #[arg(long)]
+    escape_char: Option<char>,
+    /// Skip the lines of a csv file that start with this character, e.g. '#'.
+    #[arg(long)]
+    comment_char: Option<char>,
+    /// Accept csv rows with another number of columns than the header.
+    ///
+    /// Missing columns are read as empty and extra columns are dropped. This turns off the
+    /// column count check of every row, so --lenient only skips rows that can not be read.
+    /// --min-columns still checks the header.
+    #[arg(long, default_value = "false")]
+    flexible: bool,
+    /// Skip csv rows that can not be parsed or have the wrong number of columns.
+    ///
+    /// A warning with the line and the content of the row is logged for every skipped row.
+    #[arg(long, default_value = "false")]
+    lenient: bool,
+    /// Minimum number of columns of a csv file.
+    ///
+    /// The file is not parsed if the header row, or the first row with --no-header,
+    /// has fewer columns, e.g. because the separator is wrong.
+    /// Ignored if file is not a csv file.
+    #[arg(long, value_name = "N")]
+    min_columns: Option<usize>,
+    /// Number of lines to skip at the start of a csv file, before the header row.
+    ///
+    /// For exports that start with e.g. the name of the report. With --no-header,
+    /// the first row after the skipped lines is the first issue.
+    /// Ignored if file is not a csv file.
+    #[arg(long, default_value = "0", value_name = "N")]
+    skip_rows: usize,
+    /// Match the header names of csv and xlsx files and the keys of json and yaml files exactly,
+    /// instead of ignoring case.
+    #[arg(long, default_value = "false")]
+    case_sensitive_keys: bool,
+    /// Read blocks of lines separated by empty lines from a plain text file, instead of single lines.
+    ///
+    /// The first line of a block is the title and the other lines are the description.
+    /// Ignored if file is not a plain text file.
+    #[arg(long, default_value = "false")]
+    paragraph_mode: bool,
+    /// Encoding of the file, e.g. latin1, windows-1252 or utf-16le.
+    ///
+    /// The file is decoded to UTF-8 before it is parsed. Defaults to UTF-8.
+    /// Ignored if file is an xlsx file.
+    #[arg(long)]
+    encoding: Option<String>,
+    /// Does the csv or xlsx file have a header row?
     #[arg(long, default_value = "false")]
     no_header: bool,
+    /// Name of the worksheet to read the issues from when parsing an xlsx file.
+    ///
+    /// Defaults to the first worksheet.
+    #[arg(long)]
+    sheet: Option<String>,
+    /// Index *Starting from 0* of the worksheet to read the issues from when parsing an xlsx file.
+    ///
+    /// If both sheet and sheet_index are provided, sheet_index is used.
+    #[arg(long)]
+    sheet_index: Option<usize>,
+    /// Dot separated path to the array or object with the issues when parsing a json file,
+    /// e.g. data.issues for {"data": {"issues": [...]}}.
+    ///
+    /// Defaults to the whole file. Ignored if file is not a json file.
+    #[arg(long, value_name = "PATH")]
+    json_root: Option<String>,
+    /// Use the keys of a known export format.
+    ///
+    /// github reads the title, the body as description and the labels of a GitHub issues
+    /// export. Keys given on the command line or in the config file take precedence.
+    #[arg(long, value_parser = PRESETS)]
+    preset: Option<String>,
+
     /// Key name to use as the title of the issue when parsing a csv or json file.
+    ///
+    /// Use a dot separated path, e.g. meta.summary, to read the title from nested json objects.
     #[arg(long, default_value = "title")]
     title_key: Option<String>,
     /// CSV Column index *Starting from 0* to use as the issue title.
     ///
-    /// Ignored if file is not a csv file.
+    /// Ignored if file is not a csv or xlsx file.
     /// If both title_column and title_index are provided, title_index is used.
     #[arg(long)]
     title_index: Option<usize>,
 
     /// Key name to use as the description of the issue when parsing a csv or json file.
+    ///
+    /// Use a dot separated path, e.g. body.text, to read the description from nested json objects.
     #[arg(long, default_value = "description")]
     description_key: Option<String>,
     /// Column index *Starting from 0* to use as the issue description.
     ///
-    /// Ignored if file is not a csv file.
+    /// Ignored if file is not a csv or xlsx file.
     /// If both description_column and description_index are provided, description_index is used.
     #[arg(long)]
     description_index: Option<usize>,
+    /// Comma separated key names to combine into the description, in this order, e.g. steps,expected.
+    ///
+    /// Every value is added as <key>: <value>, like with --combine-remaining.
+    /// If this is set, description_key and combine_remaining are ignored.
+    #[arg(long)]
+    description_keys: Option<String>,
+    /// Comma separated CSV column indexes *Starting from 0* to combine into the description, in this order.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both description_keys and description_indices are provided, description_indices is used.
+    #[arg(long)]
+    description_indices: Option<String>,
+    /// Treat the description column/key as the path of a file to read the description from.
+    ///
+    /// Relative paths are relative to the directory of the issues file, e.g. a
+    /// description_file column pointing to docs/issue1.md.
+    #[arg(long, default_value = "false")]
+    description_from_file: bool,
+    /// Upload the local images linked in the descriptions, e.g. ![screenshot](images/bug.png),
+    /// to the project and link the uploaded files instead.
+    ///
+    /// Relative paths are relative to the directory of the issues file.
+    /// Images that can not be uploaded are left as they are, with a warning.
+    #[arg(long, default_value = "false")]
+    upload_images: bool,
 
     /// URL of the GitLab instance, e.g. https://gitlab.com.
     #[arg(short, long, default_value = DEFAULT_GITLAB_URL)]
@@ -55,6 +210,27 @@ struct Args {
     #[arg(short, long)]
     token: Option<String>,
 
+    /// Path to a file containing the GitLab API token.
+    ///
+    /// Takes precedence over --token and the GITLAB_ACCESS_TOKEN environment variable.
+    /// On Unix, the file must not be readable by everyone.
+    #[arg(long, value_name = "TOKEN_FILE")]
+    token_file: Option<std::path::PathBuf>,
+
+    /// Extra HTTP header to send with every request, e.g. "X-Gateway-Token: secret".
+    ///
+    /// Can be given multiple times. The PRIVATE-TOKEN header can not be overridden.
+    #[arg(long = "header", value_name = "NAME: VALUE")]
+    headers: Vec<String>,
+
+    /// The token is a GitLab CI job token, sent in the JOB-TOKEN header.
+    ///
+    /// If no token is provided, the CI_JOB_TOKEN environment variable is used.
+    /// This is also done automatically when no other token is available but CI_JOB_TOKEN is set.
+    /// Job tokens can not list projects, so use --project-id or the full project path.
+    #[arg(long, default_value = "false")]
+    job_token: bool,
+
     /// Name of the gitlab project to upload to.
     ///
     /// Required if project_id is not provided.
@@ -67,121 +243,1234 @@ struct Args {
     #[arg(long)]
     project_id: Option<u64>,
 
+    /// Url of the gitlab project to upload to, e.g. https://gitlab.com/group/subgroup/project.
+    ///
+    /// Used instead of project_name or project_id. The host must be the one of --url.
+    #[arg(long)]
+    project_url: Option<String>,
+
+    /// Key name to read the project of each issue from when parsing a csv or json file.
+    ///
+    /// The project is given by name, path or id and is used instead of the project of all issues.
+    /// Issues without a project go to project_name or project_id. Assignees, labels and the
+    /// epic are verified in that project, or in the project of the first issue if none is given.
+    #[arg(long)]
+    project_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the project of each issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both project_key and project_index are provided, project_index is used.
+    #[arg(long)]
+    project_index: Option<usize>,
+    /// Skip issues whose project read from the file does not exist, instead of exiting with an error.
+    #[arg(long, default_value = "false")]
+    skip_unknown_project: bool,
+
+    /// ID of a gitlab group, the project is looked up within this group and its subgroups.
+    ///
+    /// If no project is provided, the first project of the group is used.
+    #[arg(long)]
+    group_id: Option<u64>,
+
+    /// Full path of a gitlab group, e.g. my-group or my-group/subgroup.
+    ///
+    /// Same as group_id, but the group is looked up by its path.
+    #[arg(long)]
+    group_name: Option<String>,
+
     /// Comma separated list of labels to add to the issue.
     #[arg(short, long)]
     labels: Option<String>,
 
-    /// Assignee username to add to the issue.
+    /// How --labels and the labels read from the labels column/key are combined.
+    ///
+    /// merge adds both to the issue, without duplicates. replace uses the labels of the issue
+    /// if it has any, and --labels otherwise. row-only only uses the labels of the issue and
+    /// global-only only --labels.
+    #[arg(long, default_value = "merge", value_parser = gitlabapi::LABELS_MODES)]
+    labels_mode: String,
+
+    /// Character that separates the labels of --labels and of the labels column/key, e.g. ';'.
+    #[arg(long, default_value = ",")]
+    label_separator: char,
+
+    /// Key name to read per-issue labels from when parsing a csv or json file.
+    ///
+    /// The value is a list of labels separated by label_separator, which is merged with --labels.
+    #[arg(long)]
+    labels_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read per-issue labels from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both labels_key and labels_index are provided, labels_index is used.
+    #[arg(long)]
+    labels_index: Option<usize>,
+
+    /// Key name to read the due date (YYYY-MM-DD) of the issue from when parsing a csv or json file.
+    #[arg(long)]
+    due_date_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the due date (YYYY-MM-DD) of the issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both due_date_key and due_date_index are provided, due_date_index is used.
+    #[arg(long)]
+    due_date_index: Option<usize>,
+
+    /// Creation time of all issues, a date or an ISO 8601 timestamp like 2024-01-31T12:00:00Z.
+    ///
+    /// For migrating old issues, needs the owner role in the project or an admin token.
+    #[arg(long)]
+    created_at: Option<String>,
+    /// Key name to read the creation time of the issue from when parsing a csv or json file.
+    ///
+    /// Used instead of --created-at.
+    #[arg(long)]
+    created_at_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the creation time of the issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both created_at_key and created_at_index are provided, created_at_index is used.
+    #[arg(long)]
+    created_at_index: Option<usize>,
+
+    /// Create labels given with --labels that do not exist in the project yet,
+    /// instead of exiting with an error.
+    #[arg(long, default_value = "false")]
+    create_labels: bool,
+    /// Colors of the labels created with --create-labels, e.g. "bug=#d9534f,urgent=#ff0000".
+    ///
+    /// Labels without a color get one derived from their name.
+    #[arg(long)]
+    label_color: Option<String>,
+
+    /// Assignee username or name to add to the issue.
+    ///
+    /// Same as --assignees with a single username.
     #[arg(short, long)]
     assignee: Option<String>,
 
+    /// Comma separated list of assignee usernames to add to the issue.
+    ///
+    /// The name of a member can be used instead of the username, case is ignored.
+    /// Multiple assignees require GitLab Premium.
+    #[arg(long)]
+    assignees: Option<String>,
+
+    /// Id of the user to assign the issues to.
+    ///
+    /// The id is used as is, without looking up the members of the project.
+    /// Can not be combined with --assignee or --assignees.
+    #[arg(long, value_name = "ID")]
+    assignee_id: Option<u64>,
+
+    /// Key name to read the username or name of the assignee of each issue from when parsing a csv or json file.
+    ///
+    /// The assignee of an issue is used instead of --assignees.
+    #[arg(long)]
+    assignee_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the username or name of the assignee of each issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both assignee_key and assignee_index are provided, assignee_index is used.
+    #[arg(long)]
+    assignee_index: Option<usize>,
+
+    /// Key name to read a comment of each issue from when parsing a csv or json file.
+    ///
+    /// The comment is added to the issue after it is created. An issue whose comment
+    /// can not be added is still created.
+    #[arg(long)]
+    comment_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read a comment of each issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both comment_key and comment_index are provided, comment_index is used.
+    #[arg(long)]
+    comment_index: Option<usize>,
+
+    /// Key name to read the titles of related issues from when parsing a csv or json file.
+    ///
+    /// Several titles are separated by ';', in json and yaml files they can also be a list.
+    /// The issues are linked after all issues of the file have been created, titles that
+    /// do not match a created issue are skipped with a warning.
+    #[arg(long)]
+    relates_to_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the titles of related issues from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both relates_to_key and relates_to_index are provided, relates_to_index is used.
+    #[arg(long)]
+    relates_to_index: Option<usize>,
+    /// Key name to read the titles of the issues an issue blocks from, like --relates-to-key.
+    ///
+    /// Blocking links need GitLab Premium.
+    #[arg(long)]
+    blocks_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the titles of the issues an issue blocks from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both blocks_key and blocks_index are provided, blocks_index is used.
+    #[arg(long)]
+    blocks_index: Option<usize>,
+    /// Key name to read the titles of the issues an issue is blocked by from, like --relates-to-key.
+    ///
+    /// Blocking links need GitLab Premium.
+    #[arg(long)]
+    blocked_by_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the titles of the issues an issue is blocked by from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both blocked_by_key and blocked_by_index are provided, blocked_by_index is used.
+    #[arg(long)]
+    blocked_by_index: Option<usize>,
+
+    /// Create issues whose assignee read from the file is not a member of the project
+    /// without that assignee, instead of exiting with an error.
+    #[arg(long, default_value = "false")]
+    skip_unknown_assignee: bool,
+    /// Username or name of the member to assign instead of assignees that are not members
+    /// of the project, given with --assignees or read from the file.
+    ///
+    /// Useful when the intended assignee has not been added to the project yet.
+    #[arg(long, value_name = "USERNAME")]
+    assignee_fallback: Option<String>,
+    /// Leave out assignees that are not members of the project, given with --assignees or
+    /// read from the file, with a warning instead of exiting with an error.
+    #[arg(long, default_value = "false", conflicts_with = "assignee_fallback")]
+    assignee_fallback_unset: bool,
+    /// Also accept members of the parent groups of the project as assignees.
+    ///
+    /// Use --include-inherited-members false to only accept direct members of the project.
+    #[arg(long, default_value = "true", action = ArgAction::Set, value_name = "BOOL")]
+    include_inherited_members: bool,
+
+    /// Title of the milestone to add the issue to.
+    #[arg(short, long)]
+    milestone: Option<String>,
+    /// Create the milestone given with --milestone if it does not exist in the project yet,
+    /// instead of exiting with an error.
+    #[arg(long, default_value = "false")]
+    create_milestone: bool,
+    /// Due date of the milestone created with --create-milestone, e.g. 2024-12-31.
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    milestone_due_date: Option<String>,
+
+    /// IID of an epic of the group to add the created issues to, as shown in the epic url.
+    ///
+    /// Requires --group-id or --group-name and GitLab Premium.
+    #[arg(long)]
+    epic_id: Option<u64>,
+    /// IID of an issue of the project to link the created issues to, e.g. a parent issue.
+    ///
+    /// Issues that can not be linked are still created, with a warning.
+    #[arg(long)]
+    parent_iid: Option<u64>,
+
+    /// Create all issues as confidential.
+    #[arg(long, default_value = "false")]
+    confidential: bool,
+    /// Key name to read whether the issue is confidential from when parsing a csv or json file.
+    ///
+    /// Issues with "true", "yes" or "1" in this column are created as confidential.
+    #[arg(long)]
+    confidential_key: Option<String>,
+
+    /// Weight to add to all issues.
+    ///
+    /// Weights read with --weight-key or --weight-index take precedence.
+    #[arg(long)]
+    weight: Option<u32>,
+    /// Key name to read the weight of the issue from when parsing a csv or json file.
+    #[arg(long)]
+    weight_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the weight of the issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both weight_key and weight_index are provided, weight_index is used.
+    #[arg(long)]
+    weight_index: Option<usize>,
+
+    /// Key name to read the time estimate of the issue from when parsing a csv or json file.
+    ///
+    /// The estimate is added as an /estimate quick action to the description, e.g. 2h or 1d 4h.
+    #[arg(long)]
+    estimate_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the time estimate of the issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both estimate_key and estimate_index are provided, estimate_index is used.
+    #[arg(long)]
+    estimate_index: Option<usize>,
+
+    /// Type of all issues, one of issue, incident, test_case or task.
+    ///
+    /// Types read with --issue-type-key or --issue-type-index take precedence.
+    #[arg(long, value_parser = issuefile::ISSUE_TYPES)]
+    issue_type: Option<String>,
+    /// Key name to read the type of the issue from when parsing a csv or json file.
+    #[arg(long)]
+    issue_type_key: Option<String>,
+    /// CSV Column index *Starting from 0* to read the type of the issue from.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both issue_type_key and issue_type_index are provided, issue_type_index is used.
+    #[arg(long)]
+    issue_type_index: Option<usize>,
+
+    /// Key name of a date to filter the issues by, when parsing a csv or json file.
+    ///
+    /// Issues dated outside of --filter-since and --filter-until are not created.
+    /// Dates are YYYY-MM-DD, the date of timestamps like 2024-01-31T12:00:00Z is used.
+    #[arg(long)]
+    filter_key: Option<String>,
+    /// CSV Column index *Starting from 0* of a date to filter the issues by.
+    ///
+    /// Ignored if file is not a csv or xlsx file.
+    /// If both filter_key and filter_index are provided, filter_index is used.
+    #[arg(long)]
+    filter_index: Option<usize>,
+    /// Only create issues dated on or after this date (YYYY-MM-DD).
+    #[arg(long)]
+    filter_since: Option<String>,
+    /// Only create issues dated on or before this date (YYYY-MM-DD).
+    #[arg(long)]
+    filter_until: Option<String>,
+    /// Fail on dates that can not be parsed when filtering, instead of skipping those issues.
+    #[arg(long, default_value = "false")]
+    strict: bool,
+
     /// Prepend the issue title with this string.
     /// e.g. --prepend-title "TODO:" -> "TODO: <title>"
     #[arg(long)]
     prepend_title: Option<String>,
 
+    /// Prepend the description of every issue with this string.
+    /// Issues without a description get just this string as description.
+    #[arg(long)]
+    prepend_description: Option<String>,
+
+    /// Append this string to the description of every issue.
+    /// Issues without a description get just this string as description.
+    #[arg(long)]
+    append_description: Option<String>,
+
     /// Combine all other columns/keys into the description with:
     /// <key1>: <value1>\n\n<key2>: <value2>\n\n... etc
     /// If this is set to true, description_key and description_index are ignored.
     // Double newlines because GitLab uses them to separate paragraphs.
     #[arg(long, default_value = "false")]
     combine_remaining: bool,
+    /// Create the issues with only a title, without a description.
+    ///
+    /// All other description options are ignored, e.g. when they pick up the wrong columns
+    /// and the descriptions will be filled in later.
+    #[arg(long, default_value = "false")]
+    no_description: bool,
+    /// Leave out the columns/keys with an empty value when combining them into the description
+    /// with --combine-remaining, instead of adding them as <key>: with nothing after it.
+    #[arg(long, default_value = "false")]
+    skip_empty_fields: bool,
+    /// How the columns/keys are combined into the description with --combine-remaining,
+    /// --description-keys or --description-indices.
+    ///
+    /// kv adds a <key>: <value> paragraph for every value, bullets a markdown list item
+    /// like - **<key>**: <value> and table a row of a markdown table.
+    #[arg(long, default_value = "kv", value_parser = issuefile::COMBINE_FORMATS)]
+    combine_format: String,
+
+    /// Build the description from a template, {field} is replaced with the value of that column/key.
+    /// e.g. --description-template "Reported by {reporter} on {date}\n\n{notes}"
+    /// Use column indexes, e.g. {0}, for csv files without headers.
+    /// If this is set, description_key and description_index are ignored.
+    #[arg(long)]
+    description_template: Option<String>,
+
+    /// Fail if --description-template has a placeholder that is not a column/key of the file,
+    /// instead of leaving it as it is.
+    #[arg(long, default_value = "false")]
+    strict_template: bool,
+
+    /// Replace environment variables that are not set with nothing, instead of failing.
+    ///
+    /// ${VAR} in --prepend-description, --append-description and --description-template
+    /// is replaced with the value of the environment variable VAR.
+    #[arg(long, default_value = "false")]
+    allow_undefined_env: bool,
+
+    /// How many times to retry a request that was rate limited (429) or hit a server error (5xx).
+    /// Requests that create something are only retried when rate limited.
+    #[arg(long, default_value = "3")]
+    max_retries: u32,
+    /// Wait for the rate limit of GitLab to reset when it is about to run out,
+    /// instead of creating issues until GitLab answers with 429.
+    ///
+    /// Uses the RateLimit-Remaining and RateLimit-Reset headers sent by GitLab.
+    #[arg(long, default_value = "false")]
+    respect_rate_limit: bool,
+
+    /// Seconds to wait for GitLab to answer a request before giving up.
+    #[arg(long, default_value = "30")]
+    timeout: u64,
+
+    /// Maximum number of idle connections to GitLab kept open to be reused by later requests.
+    ///
+    /// Defaults to no limit. 0 opens a new connection for every request.
+    #[arg(long, value_name = "N")]
+    pool_max_idle: Option<usize>,
+
+    /// Seconds an idle connection to GitLab is kept open to be reused, also used as
+    /// the TCP keep-alive interval.
+    #[arg(long, value_name = "SECONDS", default_value = "90")]
+    keep_alive: u64,
 
     /// Should we disable SSL verification for requests to gitlab?
     #[arg(short, long, default_value = "false")]
     no_ssl_verify: bool,
 
+    /// Path of a PEM encoded CA certificate to trust, e.g. of an internal CA.
+    ///
+    /// The certificate is trusted in addition to the system certificates.
+    #[arg(long)]
+    ca_cert: Option<std::path::PathBuf>,
+
+    /// Send all requests through this proxy, e.g. http://proxy:3128 or socks5://proxy:1080.
+    ///
+    /// Without this, the HTTPS_PROXY, HTTP_PROXY and NO_PROXY environment variables are used.
+    /// Proxies that intercept TLS may also need --ca-cert or --no-ssl-verify.
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Skip issues whose title matches an issue that is already open in the project.
+    ///
+    /// The title is compared after --prepend-title has been applied.
+    #[arg(long, default_value = "false")]
+    skip_existing: bool,
+
+    /// Make re-runs safe, e.g. after a request timed out: issues that were already created
+    /// by an earlier run are skipped.
+    ///
+    /// Every issue gets an import id, which is kept in --state-file and added to the
+    /// description as a hidden marker. Issues with the marker in the project are not created again.
+    #[arg(long, default_value = "false")]
+    idempotent: bool,
+
+    /// File to keep the import ids of --idempotent in between runs.
+    #[arg(long, default_value = statefile::DEFAULT_STATE_FILE)]
+    state_file: std::path::PathBuf,
+
+    /// Stop at the first issue that can not be created, instead of continuing with the others.
+    ///
+    /// The issues created before it are kept, the exit code is 2.
+    #[arg(long, default_value = "false")]
+    fail_fast: bool,
+
+    /// Only create the first of the issues in the file with the same title and description.
+    #[arg(long, default_value = "false")]
+    dedup: bool,
+    /// Compare issues for --dedup by title only, or by title and description (the default).
+    ///
+    /// Implies --dedup.
+    #[arg(long, value_parser = DEDUP_BY)]
+    dedup_by: Option<String>,
+
+    /// Skip issues with an empty title instead of exiting with an error.
+    #[arg(long, default_value = "false")]
+    skip_empty_titles: bool,
+
     /// Check if the file can be used to extract gitlab tasks.
     ///
     /// No checking of the gitlab instance is done.
     #[arg(short, long, default_value = "false")]
     check: bool,
+    /// Print the id, name and path of every project the token has access to and exit.
+    ///
+    /// Only the projects of the group are printed if --group-id or --group-name is given.
+    /// No file is needed.
+    #[arg(long, default_value = "false", conflicts_with = "check")]
+    list_projects: bool,
+    /// Check that GitLab can be reached with the url, token and TLS options, print the version
+    /// of GitLab and the user of the token and exit.
+    ///
+    /// No file or project is needed. The exit code is 1 if the check failed.
+    #[arg(long, default_value = "false", conflicts_with_all = ["check", "list_projects"])]
+    health_check: bool,
+
+    /// Verify the project, labels and assignee against GitLab, but only print the issues
+    /// that would be created instead of creating them.
+    #[arg(long, default_value = "false")]
+    dry_run: bool,
+    /// Let GitLab validate the issues by creating every issue and deleting it right away.
+    ///
+    /// Catches what --dry-run can not, e.g. missing permissions. Deleting issues needs the
+    /// owner role in the project. Issues are not added to epics or linked, and images are not uploaded.
+    #[arg(long, default_value = "false", conflicts_with = "dry_run")]
+    validate_roundtrip: bool,
+    /// Create the issues without asking for confirmation first.
+    ///
+    /// The number of issues and the project are shown before anything is created, and the
+    /// issues are only created if the answer is yes. Nothing is asked when stdin is not a
+    /// terminal, e.g. in CI, or with --dry-run.
+    #[arg(short, long, default_value = "false")]
+    yes: bool,
+
+    /// Output format of the results.
+    ///
+    /// With json, a json array of the issues with their title, status, iid, url and error
+    /// is printed to stdout after all issues have been handled.
+    #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+    output: String,
+    /// Write the title, status, iid, url and error of every issue to a csv or json file,
+    /// chosen by the extension.
+    #[arg(long, value_name = "PATH")]
+    report: Option<std::path::PathBuf>,
+    /// Color the summary table printed at the end of the run.
+    ///
+    /// With auto, colors are used when stdout is a terminal and the NO_COLOR
+    /// environment variable is not set.
+    #[arg(long, default_value = "auto", value_parser = COLORS)]
+    color: String,
 
     /// Verbose output.
     #[arg(short, long, default_value = "false")]
     verbose: bool,
+
+    /// Only log errors.
+    #[arg(short, long, default_value = "false", conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log every request and response sent to GitLab, including the bodies.
+    ///
+    /// The token is never logged.
+    #[arg(long, default_value = "false")]
+    trace: bool,
+
+    /// Format of the logs written to stderr.
+    ///
+    /// With json, every log line is a json object with the timestamp, level, target and
+    /// message. Logs about an issue also have its title and status, e.g. for a log aggregator.
+    #[arg(long, default_value = "text", value_parser = LOG_FORMATS)]
+    log_format: String,
+}
+
+/// Write a log record as a single line of json, for --log-format json.
+/// The key-values of the record, e.g. the title and status of an issue, are added as fields
+fn format_json_log(
+    buf: &mut env_logger::fmt::Formatter,
+    record: &log::Record,
+) -> std::io::Result<()> {
+    let mut line = serde_json::Map::new();
+    line.insert(
+        String::from("timestamp"),
+        chrono::Utc::now()
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+            .into(),
+    );
+    line.insert(String::from("level"), record.level().as_str().into());
+    line.insert(String::from("target"), record.target().into());
+    line.insert(String::from("message"), record.args().to_string().into());
+    // Visiting can not fail, every value is turned into json
+    let _ = record.key_values().visit(&mut JsonLogFields(&mut line));
+    writeln!(buf, "{}", serde_json::Value::Object(line))
+}
+
+/// Adds the key-values of a log record to a json log line
+struct JsonLogFields<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+impl<'kvs> log::kv::VisitSource<'kvs> for JsonLogFields<'_> {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        let value = match (value.to_u64(), value.to_bool()) {
+            (Some(n), _) => n.into(),
+            (None, Some(b)) => b.into(),
+            (None, None) => value.to_string().into(),
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+fn apply_config(args: &mut Args, matches: &ArgMatches, config: configfile::ConfigFile) {
+    // Only use a value from the config file if the option was not given on the command line.
+    // Options with a default value are never None, so we ask clap where the value came from.
+    macro_rules! from_config {
+        (option: $($option:ident),*; flag: $($flag:ident),*) => {
+            $(
+                if matches.value_source(stringify!($option)) != Some(ValueSource::CommandLine) {
+                    if let Some(value) = config.$option {
+                        args.$option = Some(value);
+                    }
+                }
+            )*
+            $(
+                if matches.value_source(stringify!($flag)) != Some(ValueSource::CommandLine) {
+                    if let Some(value) = config.$flag {
+                        args.$flag = value;
+                    }
+                }
+            )*
+        };
+    }
+    from_config!(
+        option: preset, separator, escape_char, comment_char, encoding, min_columns, title_key, title_index, description_key, description_index, description_keys, description_indices, url,
+            token, token_file, ca_cert, proxy, pool_max_idle, project_name, project_id, project_url, project_key, project_index, group_id, group_name, labels, label_color, labels_key, labels_index, due_date_key,
+            due_date_index, created_at, created_at_key, created_at_index, assignee, assignees, assignee_id, assignee_key, assignee_index, assignee_fallback, comment_key, comment_index, relates_to_key, relates_to_index, blocks_key, blocks_index, blocked_by_key, blocked_by_index, milestone, milestone_due_date, epic_id, parent_iid, confidential_key, weight, weight_key,
+            weight_index, estimate_key, estimate_index, issue_type, issue_type_key,
+            issue_type_index, filter_key, filter_index, filter_since, filter_until, prepend_title, prepend_description, append_description, description_template, sheet, sheet_index, json_root, report, dedup_by;
+        flag: no_header, detect_delimiter, case_sensitive_keys, paragraph_mode, quote_char, lenient, flexible, skip_rows, description_from_file, upload_images, create_labels, create_milestone, skip_unknown_assignee, assignee_fallback_unset, skip_unknown_project, include_inherited_members, labels_mode, confidential, combine_remaining, no_description, skip_empty_fields, combine_format, strict_template, allow_undefined_env, label_separator, strict,
+            skip_existing, idempotent, state_file, fail_fast, dedup, validate_roundtrip, skip_empty_titles, max_retries, respect_rate_limit, timeout, keep_alive, no_ssl_verify, job_token, color, verbose, quiet, trace, log_format
+    );
+}
+
+/// Set the keys of the --preset export format, unless they were given on the command line
+/// or in the config file
+fn apply_preset(args: &mut Args, matches: &ArgMatches) {
+    let not_given = |name: &str| matches.value_source(name) != Some(ValueSource::CommandLine);
+    if args.preset.as_deref() == Some("github") {
+        if not_given("description_key") && args.description_key.as_deref() == Some("description") {
+            args.description_key = Some(String::from("body"));
+        }
+        if args.labels_key.is_none() && args.labels_index.is_none() {
+            args.labels_key = Some(String::from("labels"));
+        }
+    }
 }
 
 fn verify_args(args: &mut Args) {
+    // Only the options to connect to GitLab are needed to list the projects or check the connection
+    if args.list_projects || args.health_check {
+        verify_gitlab_args(args);
+        return;
+    }
     // Verify that the file exists and is a file
-    if args.file.is_none() {
-        eprintln!("File must be provided");
+    let file = match args.file.as_ref() {
+        Some(f) => f,
+        None => {
+            eprintln!("File must be provided");
+            std::process::exit(1);
+        }
+    };
+    let from_stdin = file.as_os_str() == issuefile::STDIN_FILE;
+    if from_stdin && args.format.is_none() {
+        eprintln!("Format must be provided when reading from stdin");
         std::process::exit(1);
-    } else if !args.file.as_ref().unwrap().exists() {
+    } else if !from_stdin && !file.exists() {
         eprintln!("File does not exist");
         std::process::exit(1);
-    } else if !args.file.as_ref().unwrap().is_file() {
+    } else if !from_stdin && !file.is_file() {
         eprintln!("File is not a file");
         std::process::exit(1);
-    } else {
-        // Check if the file type is supported
-        let file_type = args.file.as_ref().unwrap().extension().unwrap();
-        if !issuefile::SUPPORTED_FILE_TYPES
-            .contains(&file_type.to_ascii_lowercase().to_str().unwrap())
-        {
-            eprintln!("File type is not supported");
+    }
+    // Check if the file type is supported, the format overrides the file extension
+    let file_type = match args.format.as_ref() {
+        Some(format) => format.to_lowercase(),
+        None => match file.extension().and_then(|e| e.to_str()) {
+            Some(extension) => extension.to_lowercase(),
+            None => String::new(),
+        },
+    };
+    if !issuefile::SUPPORTED_FILE_TYPES.contains(&file_type.as_str()) {
+        eprintln!("File type is not supported");
+        std::process::exit(1);
+    }
+    if let Some(report) = args.report.as_ref() {
+        let extension = report
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        if !report::SUPPORTED_REPORT_TYPES.contains(&extension.as_str()) {
+            eprintln!(
+                "report must be a csv or json file, expected one of {:?}",
+                report::SUPPORTED_REPORT_TYPES
+            );
             std::process::exit(1);
         }
-        // Set separator to None if file is not a csv file
-        if file_type != "csv" {
-            args.separator = None;
+    }
+    // Translate escape sequences like \t, the csv reader only supports single byte separators
+    if let Some(separator) = args.separator.as_ref() {
+        match parse_separator(separator) {
+            Ok(s) => args.separator = Some(s.to_string()),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
         }
     }
-    // Verify that either url is provided or GITLAB_URL is set
-    if args.url == Some(DEFAULT_GITLAB_URL.to_string()) {
-        if let Ok(url) = std::env::var("GITLAB_URL") {
-            args.url = Some(url);
-        } else {
-            eprintln!("Missing gitlab url. Either url by argument -u <URL> or GITLAB_URL environment variable must be provided");
+    // The csv reader only supports single byte quote characters
+    if !args.quote_char.is_ascii() || Some(args.quote_char.to_string()) == args.separator {
+        eprintln!("quote_char must be an ASCII character different from the separator");
+        std::process::exit(1);
+    }
+    if let Some(encoding) = args.encoding.as_ref() {
+        if encoding_rs::Encoding::for_label(encoding.trim().as_bytes()).is_none() {
+            eprintln!(
+                "Unknown encoding '{}', e.g. use latin1 or utf-16le",
+                encoding
+            );
             std::process::exit(1);
         }
     }
-    // Check if token is provided or GITLAB_ACCESS_TOKEN is set
-    if args.token.is_none() {
-        if let Ok(token) = std::env::var("GITLAB_ACCESS_TOKEN") {
-            args.token = Some(token);
+    // The csv reader only supports single byte characters, which must not mean two things
+    for c in [args.escape_char, args.comment_char].into_iter().flatten() {
+        if !c.is_ascii() || c == args.quote_char || Some(c.to_string()) == args.separator {
+            eprintln!("escape_char and comment_char must be ASCII characters different from the separator and quote_char");
+            std::process::exit(1);
         }
     }
-    // Verify that either project_name or project_id is provided
-    if args.project_name.is_none() && args.project_id.is_none() {
-        eprintln!("Either project_name or project_id must be provided");
+    if args.escape_char.is_some() && args.escape_char == args.comment_char {
+        eprintln!("escape_char and comment_char must be different");
+        std::process::exit(1);
+    }
+    // Set separator to None if file is not a csv file, tsv files always use a tab
+    if file_type != "csv" {
+        args.separator = None;
+    }
+    verify_gitlab_args(args);
+    // We can not prompt for the token if stdin is used for the issues
+    if from_stdin && args.token.is_none() && args.token_file.is_none() && !args.check {
+        eprintln!("Token must be provided by argument or GITLAB_ACCESS_TOKEN environment variable when reading from stdin");
+        std::process::exit(1);
+    }
+    // The project url is turned into the path of the project, which is looked up like a project name
+    if let Some(project_url) = args.project_url.as_ref() {
+        if args.project_name.is_some() || args.project_id.is_some() {
+            eprintln!("Only one of project_url, project_name or project_id can be provided");
+            std::process::exit(1);
+        }
+        match project_path_from_url(project_url, args.url.as_ref().unwrap()) {
+            Ok(path) => args.project_name = Some(path),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.project_index.is_some() {
+        args.project_key = None;
+    }
+    // Verify that either project_name or project_id is provided, a group or
+    // the project of every issue can be used instead
+    let group_given = args.group_id.is_some() || args.group_name.is_some();
+    let row_projects_given = args.project_key.is_some() || args.project_index.is_some();
+    if args.project_name.is_none()
+        && args.project_id.is_none()
+        && !group_given
+        && !row_projects_given
+    {
+        eprintln!(
+            "Either project_name, project_id, project_key, group_name or group_id must be provided"
+        );
         std::process::exit(1);
     }
     if args.project_name.is_some() && args.project_id.is_some() {
         eprintln!("Only one of project_name or project_id can be provided");
         std::process::exit(1);
     }
+    if args.group_name.is_some() && args.group_id.is_some() {
+        eprintln!("Only one of group_name or group_id can be provided");
+        std::process::exit(1);
+    }
+    // Epics belong to a group
+    if args.epic_id.is_some() && !group_given {
+        eprintln!("epic_id requires group_name or group_id");
+        std::process::exit(1);
+    }
+    // Labels are passed on comma separated, like GitLab expects them
+    if let Some(labels) = args.labels.as_ref() {
+        match issuefile::normalize_labels(labels, args.label_separator, "the command line") {
+            Ok(l) => args.labels = Some(l),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
     // Verify that labels is a comma separated list
-    if args.labels.is_some() {
-        let labels = args.labels.as_ref().unwrap();
-        if labels.contains(",") {
-            let labels: Vec<&str> = labels.split(",").collect();
+    if let Some(labels) = args.labels.as_ref() {
+        if labels.contains(',') {
+            let labels: Vec<&str> = labels.split(',').collect();
             for label in labels {
                 if label.is_empty() {
-                    eprintln!("Labels must be a comma separated list of non-empty strings");
+                    eprintln!(
+                        "Labels must be a list of non-empty strings, separated by label_separator"
+                    );
                     std::process::exit(1);
                 }
             }
         }
     }
+    if let Some(label_color) = args.label_color.as_ref() {
+        if let Err(e) = parse_label_colors(label_color) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    if args.assignee_id.is_some() && (args.assignee.is_some() || args.assignees.is_some()) {
+        eprintln!("assignee_id can not be combined with assignee or assignees");
+        std::process::exit(1);
+    }
+    // --assignee is a single value alias of --assignees
+    if let Some(assignee) = args.assignee.take() {
+        args.assignees = match args.assignees.take() {
+            Some(assignees) => Some(format!("{},{}", assignee, assignees)),
+            None => Some(assignee),
+        };
+    }
+    // Verify that assignees is a comma separated list
+    if let Some(assignees) = args.assignees.as_ref() {
+        if assignees
+            .split(',')
+            .any(|assignee| assignee.trim().is_empty())
+        {
+            eprintln!("Assignees must be a comma separated list of non-empty usernames");
+            std::process::exit(1);
+        }
+    }
     // Clear title and description column if index is provided
     if args.title_index.is_some() {
         args.title_key = None;
     }
+    // No description column is needed for title only issues
+    if args.no_description {
+        args.description_key = None;
+        args.description_index = None;
+        args.description_keys = None;
+        args.description_indices = None;
+        args.description_template = None;
+        args.description_from_file = false;
+        args.combine_remaining = false;
+        args.prepend_description = None;
+        args.append_description = None;
+        args.upload_images = false;
+    }
     if args.description_index.is_some() {
         args.description_key = None;
     }
+    // The chosen description columns take the place of the description column
+    if args.description_indices.is_some() {
+        args.description_keys = None;
+    }
+    if let Some(indices) = args.description_indices.as_ref() {
+        if indices
+            .split(',')
+            .any(|i| i.trim().parse::<usize>().is_err())
+        {
+            eprintln!("description_indices must be a comma separated list of column indexes");
+            std::process::exit(1);
+        }
+    }
+    if args.description_keys.is_some() || args.description_indices.is_some() {
+        if args.description_template.is_some() {
+            eprintln!("Only one of description_template or description_keys/description_indices can be provided");
+            std::process::exit(1);
+        }
+        args.description_key = None;
+        args.description_index = None;
+        args.combine_remaining = false;
+    }
+    // The template takes the place of the description column
+    if args.description_template.is_some() {
+        if args.combine_remaining {
+            eprintln!("Only one of description_template or combine_remaining can be provided");
+            std::process::exit(1);
+        }
+        args.description_key = None;
+        args.description_index = None;
+    }
+    // Expand ${VAR} with the environment, e.g. for a footer with the name of the user
+    for text in [
+        &mut args.prepend_description,
+        &mut args.append_description,
+        &mut args.description_template,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        match issuefile::expand_env(
+            text,
+            |name| std::env::var(name).ok(),
+            args.allow_undefined_env,
+        ) {
+            Ok(expanded) => *text = expanded,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.labels_index.is_some() {
+        args.labels_key = None;
+    }
+    if args.due_date_index.is_some() {
+        args.due_date_key = None;
+    }
+    if args.created_at_index.is_some() {
+        args.created_at_key = None;
+    }
+    if let Some(created_at) = args.created_at.as_ref() {
+        match issuefile::parse_created_at(created_at, "the command line") {
+            Ok(c) => args.created_at = c,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.weight_index.is_some() {
+        args.weight_key = None;
+    }
+    if args.estimate_index.is_some() {
+        args.estimate_key = None;
+    }
+    if args.issue_type_index.is_some() {
+        args.issue_type_key = None;
+    }
+    if args.assignee_index.is_some() {
+        args.assignee_key = None;
+    }
+    if args.comment_index.is_some() {
+        args.comment_key = None;
+    }
+    if args.relates_to_index.is_some() {
+        args.relates_to_key = None;
+    }
+    if args.blocks_index.is_some() {
+        args.blocks_key = None;
+    }
+    if args.blocked_by_index.is_some() {
+        args.blocked_by_key = None;
+    }
+    if args.filter_index.is_some() {
+        args.filter_key = None;
+    }
+    // The filter dates need a date to compare with and must be valid dates
+    let filter_given = args.filter_key.is_some() || args.filter_index.is_some();
+    if (args.filter_since.is_some() || args.filter_until.is_some()) != filter_given {
+        eprintln!("filter_key or filter_index must be provided together with filter_since or filter_until");
+        std::process::exit(1);
+    }
+    for date in [args.filter_since.as_ref(), args.filter_until.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        if issuefile::parse_date(date).is_none() {
+            eprintln!("Invalid filter date '{}', expected YYYY-MM-DD", date);
+            std::process::exit(1);
+        }
+    }
+    if let Some(due_date) = args.milestone_due_date.as_ref() {
+        if !args.create_milestone {
+            eprintln!("milestone_due_date can only be used together with create_milestone");
+            std::process::exit(1);
+        }
+        if chrono::NaiveDate::parse_from_str(due_date, "%Y-%m-%d").is_err() {
+            eprintln!(
+                "Invalid milestone due date '{}', expected YYYY-MM-DD",
+                due_date
+            );
+            std::process::exit(1);
+        }
+    }
+    // The issue type is validated by clap, but not when it comes from the config file
+    if let Some(issue_type) = args.issue_type.as_ref() {
+        if !issuefile::ISSUE_TYPES.contains(&issue_type.as_str()) {
+            eprintln!(
+                "Invalid issue type '{}', expected one of {:?}",
+                issue_type,
+                issuefile::ISSUE_TYPES
+            );
+            std::process::exit(1);
+        }
+    }
+    if let Some(dedup_by) = args.dedup_by.as_ref() {
+        if !DEDUP_BY.contains(&dedup_by.as_str()) {
+            eprintln!(
+                "Invalid dedup_by '{}', expected one of {:?}",
+                dedup_by, DEDUP_BY
+            );
+            std::process::exit(1);
+        }
+    }
+    // Like the issue type, these choices are only validated by clap on the command line
+    let choices: [(&str, Option<&String>, &[&str]); 4] = [
+        (
+            "combine_format",
+            Some(&args.combine_format),
+            &issuefile::COMBINE_FORMATS,
+        ),
+        ("preset", args.preset.as_ref(), &PRESETS),
+        ("color", Some(&args.color), &COLORS),
+        ("log_format", Some(&args.log_format), &LOG_FORMATS),
+    ];
+    for (name, value, allowed) in choices {
+        if let Some(value) = value.filter(|v| !allowed.contains(&v.as_str())) {
+            eprintln!(
+                "Invalid {} '{}', expected one of {:?}",
+                name, value, allowed
+            );
+            std::process::exit(1);
+        }
+    }
+    if !gitlabapi::LABELS_MODES.contains(&args.labels_mode.as_str()) {
+        eprintln!(
+            "Invalid labels_mode '{}', expected one of {:?}",
+            args.labels_mode,
+            gitlabapi::LABELS_MODES
+        );
+        std::process::exit(1);
+    }
+    if args.sheet_index.is_some() {
+        args.sheet = None;
+    }
     // Verify that title_index is provided if the csv file has no header
     if args.no_header && args.title_index.is_none() {
         eprintln!("title_index must be provided if the csv file has no header");
         std::process::exit(1);
     }
-    debug!("Running with args: {:?}", args);
+    // Never log the token
+    let mut shown_args = args.clone();
+    if shown_args.token.is_some() {
+        shown_args.token = Some(String::from("***"));
+    }
+    debug!("Running with args: {:?}", shown_args);
+}
+
+/// Verify the options to connect to GitLab
+fn verify_gitlab_args(args: &mut Args) {
+    // Verify that either url is provided or GITLAB_URL is set
+    if args.url == Some(DEFAULT_GITLAB_URL.to_string()) {
+        if let Ok(url) = std::env::var("GITLAB_URL") {
+            args.url = Some(url);
+        } else {
+            eprintln!("Missing gitlab url. Either url by argument -u <URL> or GITLAB_URL environment variable must be provided");
+            std::process::exit(1);
+        }
+    }
+    // Check if token is provided or GITLAB_ACCESS_TOKEN is set
+    if args.token.is_none() && !args.job_token {
+        if let Ok(token) = std::env::var("GITLAB_ACCESS_TOKEN") {
+            args.token = Some(token);
+        }
+    }
+    // Fall back to the CI job token, e.g. when running in a GitLab pipeline
+    if args.token.is_none() && args.token_file.is_none() {
+        if let Ok(token) = std::env::var("CI_JOB_TOKEN") {
+            if !args.job_token {
+                debug!("Using CI_JOB_TOKEN as a job token");
+            }
+            args.token = Some(token);
+            args.job_token = true;
+        }
+    }
+    if args.timeout == 0 {
+        eprintln!("timeout must be at least 1 second");
+        std::process::exit(1);
+    }
+}
+
+/// Turn --separator into the character it stands for, e.g. \t into a tab
+fn parse_separator(separator: &str) -> Result<char, String> {
+    let escaped = separator.strip_prefix('\\');
+    let mut chars = escaped.unwrap_or(separator).chars();
+    let separator = match (chars.next(), chars.next(), escaped.is_some()) {
+        (Some('t'), None, true) => '\t',
+        (Some('0'), None, true) => '\0',
+        (Some(c), None, _) => c,
+        _ => {
+            return Err(format!(
+            "Invalid separator '{}', expected a single character or an escape sequence like \\t",
+            separator
+        ))
+        }
+    };
+    if !separator.is_ascii() {
+        return Err(format!(
+            "Invalid separator '{}', the separator must be a single ASCII character because the csv reader only supports single byte separators",
+            separator
+        ));
+    }
+    Ok(separator)
+}
+
+/// Upload the local images linked in a description, e.g. ![screenshot](images/bug.png),
+/// and replace the links with the markdown of the uploaded files.
+/// Images that can not be uploaded are left as they are
+fn upload_images(
+    client: &gitlabapi::GitLabApiRequest,
+    project_id: u64,
+    description: &str,
+    base_dir: Option<&std::path::Path>,
+) -> String {
+    let mut result = String::new();
+    let mut rest = description;
+    while let Some(start) = rest.find("![") {
+        // The link is ![alt](target), the target may have a title after the path
+        let link = rest[start..].find("](").and_then(|middle| {
+            let end = rest[start + middle..].find(')')?;
+            Some((start + middle + 2, start + middle + end))
+        });
+        let (target_start, end) = match link {
+            Some(l) => l,
+            None => break,
+        };
+        let target = rest[target_start..end]
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        let target = target.trim_start_matches('<').trim_end_matches('>');
+        result.push_str(&rest[..start]);
+        let is_local = !target.is_empty()
+            && !target.contains("://")
+            && !target.starts_with("/uploads/")
+            && !target.starts_with("data:");
+        let replacement = match is_local {
+            true => {
+                let path = match base_dir {
+                    Some(dir) => dir.join(target),
+                    None => std::path::PathBuf::from(target),
+                };
+                match client.upload_file(project_id, &path) {
+                    Ok(markdown) => {
+                        info!("Uploaded {} as {}", path.display(), markdown);
+                        Some(markdown)
+                    }
+                    Err(e) => {
+                        warn!("Could not upload image {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            }
+            false => None,
+        };
+        match replacement {
+            Some(markdown) => result.push_str(&markdown),
+            None => result.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Parse --label-color, e.g. "bug=#d9534f,urgent=#ff0000", into the color of every label
+fn parse_label_colors(label_color: &str) -> Result<HashMap<String, String>, String> {
+    let mut colors = HashMap::new();
+    for entry in label_color.split(',') {
+        let (label, color) = match entry.split_once('=') {
+            Some((label, color)) if !label.trim().is_empty() => (label.trim(), color.trim()),
+            _ => {
+                return Err(format!(
+                    "Invalid label color '{}', expected e.g. bug=#d9534f",
+                    entry
+                ))
+            }
+        };
+        // GitLab takes colors as #RGB or #RRGGBB
+        let hex = color.strip_prefix('#').unwrap_or_default();
+        if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "Invalid color '{}' for label '{}', expected a hex color like #d9534f",
+                color, label
+            ));
+        }
+        colors.insert(label.to_string(), color.to_string());
+    }
+    Ok(colors)
+}
+
+/// Find the member with the assignee as username or name, ignoring case.
+/// A username wins over a name, a name shared by several members is an error
+fn find_assignee<'a>(
+    members: &'a [gitlabapi::GitLabProjectMember],
+    assignee: &str,
+) -> Result<Option<&'a gitlabapi::GitLabProjectMember>, String> {
+    if let Some(member) = members
+        .iter()
+        .find(|member| member.username.eq_ignore_ascii_case(assignee))
+    {
+        return Ok(Some(member));
+    }
+    let named: Vec<&gitlabapi::GitLabProjectMember> = members
+        .iter()
+        .filter(|member| member.name.to_lowercase() == assignee.to_lowercase())
+        .collect();
+    match named.as_slice() {
+        [] => Ok(None),
+        [member] => Ok(Some(member)),
+        _ => Err(format!(
+            "The assignee '{}' is ambiguous, it is the name of the members {}. Use the username instead",
+            assignee,
+            named
+                .iter()
+                .map(|member| member.username.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Get the path of a project, e.g. group/subgroup/project, from its url.
+/// The url must point to the same GitLab as gitlab_url, which can be installed under a path.
+fn project_path_from_url(project_url: &str, gitlab_url: &str) -> Result<String, String> {
+    let parse = |url: &str| match reqwest::Url::parse(url.trim()) {
+        Ok(u) => Ok(u),
+        Err(e) => Err(format!("Invalid url '{}': {}", url, e)),
+    };
+    let project = parse(project_url)?;
+    let gitlab = parse(gitlab_url)?;
+    if project.host_str() != gitlab.host_str() {
+        return Err(format!(
+            "The project url '{}' is not on the GitLab instance {}",
+            project_url, gitlab_url
+        ));
+    }
+    let path = project
+        .path()
+        .strip_prefix(gitlab.path().trim_end_matches('/'))
+        .unwrap_or_default();
+    // Drop pages of the project, e.g. /-/issues, and the .git of clone urls
+    let path = path.split("/-/").next().unwrap_or_default();
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if !path.contains('/') {
+        return Err(format!(
+            "Could not find the path of the project in '{}', expected e.g. {}/group/project",
+            project_url,
+            gitlab_url.trim_end_matches('/')
+        ));
+    }
+    Ok(path.to_string())
+}
+
+fn read_token_file(path: &std::path::Path) -> Result<String, String> {
+    // Refuse tokens that every user on the machine can read
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            if metadata.permissions().mode() & 0o004 != 0 {
+                warn!(
+                    "Token file {} is readable by everyone, restrict it with chmod 600",
+                    path.display()
+                );
+                return Err(String::from("Refusing to use a world-readable token file"));
+            }
+        }
+    }
+    let token = match std::fs::read_to_string(path) {
+        Ok(t) => t.trim().to_string(),
+        Err(e) => return Err(format!("Could not read token file: {}", e)),
+    };
+    if token.is_empty() {
+        return Err(format!("Token file {} is empty", path.display()));
+    }
+    Ok(token)
 }
 
 fn ask_user_for_token() -> Result<String, &'static str> {
     let mut buffer = String::new();
-    println!("No token provided. Please enter your GitLab API token:");
+    eprintln!("No token provided. Please enter your GitLab API token:");
     let token = match std::io::stdin().read_line(&mut buffer) {
         Ok(_) => buffer.trim().to_string(),
         Err(_) => return Err("Could not read token"),
@@ -189,27 +1478,124 @@ fn ask_user_for_token() -> Result<String, &'static str> {
     Ok(token)
 }
 
-fn args_to_parser(args: &Args) -> issuefile::FileParser {
-    let parser = issuefile::FileParser::new(
-        args.file.as_ref().unwrap().to_path_buf(),
-        args.separator.clone(),
-        args.no_header.clone(),
-        args.title_key.clone(),
-        args.title_index,
-        args.description_key.clone(),
-        args.description_index,
-        args.prepend_title.clone(),
-        args.combine_remaining,
+/// Print what a dry run would do. With --output json stdout only has the json results,
+/// so the messages go to stderr instead
+fn print_dry_run(args: &Args, message: &str) {
+    match args.output.as_str() {
+        "json" => eprintln!("{}", message),
+        _ => println!("{}", message),
+    }
+}
+
+/// Ask the user to confirm the creation of the issues, anything but yes is a no
+fn confirm_creation(count: usize, projects: &[String]) -> bool {
+    let target = match projects {
+        [project] => format!("project {}", project),
+        _ => format!("projects {}", projects.join(", ")),
+    };
+    eprint!(
+        "About to create {} issues in {}. Continue? [y/N] ",
+        count, target
     );
-    parser
+    let mut buffer = String::new();
+    match std::io::stdin().read_line(&mut buffer) {
+        Ok(_) => matches!(buffer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
 }
 
-fn args_to_gitlabapi_request_client(
-    args: &Args,
-) -> Result<gitlabapi::GitLabApiRequest, &'static str> {
-    let token: String = match args.token.as_ref() {
-        Some(t) => t.clone(),
-        None => {
+fn args_to_parser(args: &Args) -> issuefile::FileParser {
+    issuefile::FileParser::new(issuefile::ParserOptions {
+        file: args.file.as_ref().unwrap().to_path_buf(),
+        // Translated to a single character in verify_args
+        separator: args.separator.as_ref().and_then(|s| s.chars().next()),
+        no_header: args.no_header,
+        title_key: args.title_key.clone(),
+        title_column_index: args.title_index,
+        description_key: args.description_key.clone(),
+        description_column_index: args.description_index,
+        prepend_title: args.prepend_title.clone(),
+        combine_remaining: args.combine_remaining,
+        no_description: args.no_description,
+        labels_key: args.labels_key.clone(),
+        labels_column_index: args.labels_index,
+        due_date_key: args.due_date_key.clone(),
+        due_date_column_index: args.due_date_index,
+        confidential_key: args.confidential_key.clone(),
+        format: args.format.clone(),
+        weight_key: args.weight_key.clone(),
+        weight_column_index: args.weight_index,
+        sheet: args.sheet.clone(),
+        sheet_index: args.sheet_index,
+        skip_empty_titles: args.skip_empty_titles,
+        estimate_key: args.estimate_key.clone(),
+        estimate_column_index: args.estimate_index,
+        detect_delimiter: args.detect_delimiter,
+        description_from_file: args.description_from_file,
+        issue_type_key: args.issue_type_key.clone(),
+        issue_type_column_index: args.issue_type_index,
+        lenient: args.lenient,
+        quote_char: args.quote_char,
+        prepend_description: args.prepend_description.clone(),
+        append_description: args.append_description.clone(),
+        description_template: args.description_template.clone(),
+        strict_template: args.strict_template,
+        label_separator: args.label_separator,
+        filter_key: args.filter_key.clone(),
+        filter_column_index: args.filter_index,
+        // The dates were validated in verify_args
+        filter_since: args.filter_since.as_deref().and_then(issuefile::parse_date),
+        filter_until: args.filter_until.as_deref().and_then(issuefile::parse_date),
+        strict: args.strict,
+        description_keys: args
+            .description_keys
+            .as_ref()
+            .map(|keys| keys.split(',').map(|k| k.trim().to_string()).collect()),
+        // The indices were validated in verify_args
+        description_column_indices: args.description_indices.as_ref().map(|indices| {
+            indices
+                .split(',')
+                .map(|i| i.trim().parse().unwrap())
+                .collect()
+        }),
+        assignee_key: args.assignee_key.clone(),
+        assignee_column_index: args.assignee_index,
+        min_columns: args.min_columns,
+        case_sensitive_keys: args.case_sensitive_keys,
+        created_at_key: args.created_at_key.clone(),
+        created_at_column_index: args.created_at_index,
+        paragraph_mode: args.paragraph_mode,
+        project_key: args.project_key.clone(),
+        project_column_index: args.project_index,
+        skip_empty_fields: args.skip_empty_fields,
+        combine_format: args.combine_format.clone(),
+        json_root: args.json_root.clone(),
+        comment_key: args.comment_key.clone(),
+        comment_column_index: args.comment_index,
+        // The encoding was validated in verify_args
+        encoding: args
+            .encoding
+            .as_ref()
+            .and_then(|e| encoding_rs::Encoding::for_label(e.trim().as_bytes())),
+        relates_to_key: args.relates_to_key.clone(),
+        relates_to_column_index: args.relates_to_index,
+        blocks_key: args.blocks_key.clone(),
+        blocks_column_index: args.blocks_index,
+        blocked_by_key: args.blocked_by_key.clone(),
+        blocked_by_column_index: args.blocked_by_index,
+        skip_rows: args.skip_rows,
+        escape_char: args.escape_char,
+        comment_char: args.comment_char,
+        flexible: args.flexible,
+    })
+}
+
+fn args_to_gitlabapi_request_client(args: &Args) -> Result<gitlabapi::GitLabApiRequest, String> {
+    // The token file takes precedence, args.token already falls back to GITLAB_ACCESS_TOKEN
+    let token: String = match (args.token_file.as_ref(), args.token.as_ref()) {
+        (Some(path), _) => read_token_file(path)?,
+        (None, Some(t)) => t.clone(),
+        (None, None) => {
             let token = loop {
                 match ask_user_for_token() {
                     Ok(t) => break t,
@@ -219,12 +1605,24 @@ fn args_to_gitlabapi_request_client(
             token
         }
     };
-    let client = gitlabapi::GitLabApiRequest::new(
+    match gitlabapi::GitLabApiRequest::new(
         args.url.as_ref().unwrap().as_str(),
         token,
         args.no_ssl_verify,
-    );
-    Ok(client)
+        args.max_retries,
+        args.trace,
+        &args.headers,
+        args.job_token,
+        args.ca_cert.as_deref(),
+        args.timeout,
+        args.proxy.as_deref(),
+        args.respect_rate_limit,
+        args.pool_max_idle,
+        args.keep_alive,
+    ) {
+        Ok(client) => Ok(client),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 fn get_valid_project_id(
@@ -232,67 +1630,294 @@ fn get_valid_project_id(
     projects: Vec<gitlabapi::GitLabProject>,
 ) -> Result<u64, String> {
     // Check if the user provided project name or id
-    if args.project_name.is_some() {
-        let wanted_project_name = args.project_name.as_ref().unwrap();
-        // It is possible that the user provided a project name,
-        // for which there are multiple projects with the same name.
-        // Check for name and namespace
-        let mut matching_projects: Vec<u64> = Vec::new();
-        projects.iter().for_each(|project| {
-            if &project.name == wanted_project_name {
-                matching_projects.push(project.id);
-            }
-            if &project.path_with_namespace == wanted_project_name {
-                matching_projects.push(project.id);
+    if let Some(wanted_project_name) = args.project_name.as_ref() {
+        match matching_projects(&projects, wanted_project_name).as_slice() {
+            [] => Err(format!(
+                "No projects with name '{}' found",
+                wanted_project_name
+            )),
+            [project] => Ok(project.id),
+            // Let someone at a terminal choose, instead of starting over
+            many if !args.yes && std::io::stdin().is_terminal() => {
+                pick_project(wanted_project_name, many)
             }
-        });
+            many => Err(ambiguous_project_error(wanted_project_name, many)),
+        }
+    } else {
+        // args.project_id.is_some() is always true if we reach this point
+        let wanted_project_id = args.project_id.unwrap();
+        for project in projects {
+            if project.id == wanted_project_id {
+                return Ok(wanted_project_id);
+            }
+        }
+        Err(format!("No project with id '{}' found", wanted_project_id))
+    }
+}
+
+fn find_project_by_name(
+    projects: &[gitlabapi::GitLabProject],
+    wanted_project_name: &str,
+) -> Result<Option<u64>, String> {
+    match matching_projects(projects, wanted_project_name).as_slice() {
+        [] => Ok(None),
+        [project] => Ok(Some(project.id)),
+        many => Err(ambiguous_project_error(wanted_project_name, many)),
+    }
+}
+
+/// The projects with the given name or path.
+/// It is possible that the user provided a project name,
+/// for which there are multiple projects with the same name.
+fn matching_projects<'a>(
+    projects: &'a [gitlabapi::GitLabProject],
+    wanted_project_name: &str,
+) -> Vec<&'a gitlabapi::GitLabProject> {
+    projects
+        .iter()
+        .filter(|project| {
+            project.name == wanted_project_name
+                || project.path_with_namespace == wanted_project_name
+        })
+        .collect()
+}
+
+/// List the projects with the same name, so the user can choose one by id or path
+fn ambiguous_project_error(
+    wanted_project_name: &str,
+    projects: &[&gitlabapi::GitLabProject],
+) -> String {
+    let mut message = format!(
+        "Multiple projects with name '{}' found:",
+        wanted_project_name
+    );
+    for project in projects {
+        message.push_str(&format!(
+            "\n\t{}: {}",
+            project.id, project.path_with_namespace
+        ));
+    }
+    message.push_str(&format!(
+        "\nUse --project-id or the full path of the project instead, e.g. '{}'",
+        projects[0].path_with_namespace
+    ));
+    message
+}
+
+/// Let the user choose one of the projects with the same name
+fn pick_project(
+    wanted_project_name: &str,
+    projects: &[&gitlabapi::GitLabProject],
+) -> Result<u64, String> {
+    eprintln!(
+        "Multiple projects with name '{}' found:",
+        wanted_project_name
+    );
+    for (n, project) in projects.iter().enumerate() {
+        eprintln!(
+            "\t{}) {} (id {})",
+            n + 1,
+            project.path_with_namespace,
+            project.id
+        );
+    }
+    eprint!("Choose a project [1-{}]: ", projects.len());
+    let mut buffer = String::new();
+    let choice = match std::io::stdin().read_line(&mut buffer) {
+        Ok(_) => buffer.trim().parse::<usize>().ok(),
+        Err(_) => None,
+    };
+    match choice {
+        Some(n) if (1..=projects.len()).contains(&n) => Ok(projects[n - 1].id),
+        _ => Err(ambiguous_project_error(wanted_project_name, projects)),
+    }
+}
+
+/// Find the id of the project of an issue, given by id, path or name.
+/// None if there is no such project. Names are looked up in the projects of the group,
+/// or all projects of the token, which are listed once. The paths of the projects that
+/// were looked up are added to project_paths
+fn resolve_issue_project(
+    client: &gitlabapi::GitLabApiRequest,
+    group: Option<&gitlabapi::GitLabGroup>,
+    project: &str,
+    listed_projects: &mut Option<Vec<gitlabapi::GitLabProject>>,
+    project_paths: &mut HashMap<u64, String>,
+) -> Result<Option<u64>, String> {
+    // GitLab looks up ids the same way as paths
+    if project.contains('/') || project.parse::<u64>().is_ok() {
+        return match client.get_project_by_path(project) {
+            Ok(p) => {
+                debug!("Project '{}' is {}", project, p);
+                project_paths.insert(p.id, p.path_with_namespace);
+                Ok(Some(p.id))
+            }
+            Err(gitlabapi::GitLabApiError::Http { status: 404, .. }) => Ok(None),
+            Err(e) => Err(format!("Could not find project '{}': {}", project, e)),
+        };
+    }
+    if listed_projects.is_none() {
+        let projects = match group {
+            Some(g) => client.get_projects_of_group(g.id),
+            None => client.get_projects(),
+        };
+        let projects = projects.map_err(|e| e.to_string())?;
+        project_paths.extend(
+            projects
+                .iter()
+                .map(|p| (p.id, p.path_with_namespace.clone())),
+        );
+        *listed_projects = Some(projects);
+    }
+    find_project_by_name(listed_projects.as_ref().unwrap(), project)
+}
+
+/// Print the version of GitLab and the user of the token, exit with a hint at the cause
+/// if either can not be read
+fn health_check(client: &gitlabapi::GitLabApiRequest, args: &Args) {
+    let fail = |what: &str, e: gitlabapi::GitLabApiError| -> ! {
+        error!(
+            "Could not get the {} of GitLab at {}: {}",
+            what,
+            args.url.as_ref().unwrap(),
+            e
+        );
+        if let Some(hint) = e.hint() {
+            error!("{}", hint);
+        }
+        std::process::exit(1);
+    };
+    match client.get_version() {
+        Ok(version) => println!("GitLab version: {}", version),
+        Err(e) => fail("version", e),
+    }
+    // Job tokens can not access the user endpoint
+    if args.job_token {
+        println!("Authenticated with a job token");
+        return;
+    }
+    match client.get_current_user() {
+        Ok(user) => println!("Authenticated as: {}", user.username),
+        Err(e) => fail("user", e),
+    }
+}
+
+/// Print the projects the token has access to, or the projects of the group
+fn list_projects(client: &gitlabapi::GitLabApiRequest, args: &Args) {
+    let group = match (args.group_id, args.group_name.as_ref()) {
+        (Some(id), _) => Some(id.to_string()),
+        (None, Some(path)) => Some(path.clone()),
+        (None, None) => None,
+    };
+    let projects = match group {
+        Some(group) => client
+            .get_group(&group)
+            .and_then(|group| client.get_projects_of_group(group.id)),
+        None => client.get_projects(),
+    };
+    match projects {
+        Ok(projects) => {
+            info!("Found {} projects", projects.len());
+            projects.iter().for_each(|project| println!("{}", project));
+        }
+        Err(e) => {
+            error!("Could not list projects: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
 
-        match matching_projects.len() {
-            0 => {
-                return Err(format!(
-                    "No projects with name '{}' found",
-                    wanted_project_name.clone()
-                ))
-            }
-            1 => {
-                return Ok(matching_projects[0]);
-            }
-            _ => {
-                return Err(format!(
-                    "Multiple projects with name '{}' found",
-                    wanted_project_name.clone()
-                ));
-            }
-        };
-    } else {
-        // args.project_id.is_some() is always true if we reach this point
-        let wanted_project_id = args.project_id.unwrap();
-        for project in projects {
-            if project.id == wanted_project_id {
-                return Ok(wanted_project_id);
-            }
+/// Exit early if the token can not be used to authenticate with GitLab.
+/// Job tokens can not access the user endpoint, so they are not checked
+fn verify_token(client: &gitlabapi::GitLabApiRequest, args: &Args) {
+    if args.job_token {
+        debug!("Using a job token, skipping token check");
+        return;
+    }
+    debug!("Checking token...");
+    match client.get_current_user() {
+        Ok(user) => debug!("Authenticated as {}", user),
+        Err(gitlabapi::GitLabApiError::Http {
+            status: 401 | 403, ..
+        }) => {
+            error!("Could not authenticate with GitLab: token invalid or insufficient scope");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            error!("Could not check token: {}", e);
+            std::process::exit(1);
         }
-        return Err(format!("No project with id '{}' found", wanted_project_id));
     }
 }
 
 fn main() {
-    let mut args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // Fill in the options that were not given on the command line from the config file
+    if let Some(config_path) = args.config.clone() {
+        match configfile::ConfigFile::from_path(&config_path) {
+            Ok(config) => apply_config(&mut args, &matches, config),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    apply_preset(&mut args, &matches);
     // Decide fefault log level if user wants to see verbose output
-    let log_level = if args.verbose { "info" } else { "warn" };
+    let log_level = match (args.verbose, args.quiet) {
+        (true, _) => "info",
+        (false, true) => "error",
+        (false, false) => "warn",
+    };
+    // --trace only turns on trace logs of this crate, not of the http libraries
+    let log_level = match args.trace {
+        true => format!("{},{}=trace", log_level, env!("CARGO_CRATE_NAME")),
+        false => log_level.to_string(),
+    };
     // Set up logging and use log_level as default log level,
     // if it is not specified by the RUST_LOG env var
     let e = env_logger::Env::default().filter_or("RUST_LOG", log_level);
     let mut builder = env_logger::Builder::from_env(e);
+    if args.log_format == "json" {
+        builder.format(format_json_log);
+    }
     // Initialize the logger
     builder
         .format_timestamp(None) // Remove timestamp from log output
-        .target(env_logger::Target::Stdout) // Log to stdout instead of stderr
+        .target(env_logger::Target::Stderr) // Keep stdout for the output, e.g. --output json
         .init();
 
     // Verify that the arguments are valid
     verify_args(&mut args);
 
+    // Create the gitlab api client and check that the token works before parsing the file,
+    // so a bad token does not fail every issue one by one. --check does not need GitLab at all
+    let client = match args.check {
+        true => None,
+        false => {
+            debug!("Creating GitLab API client...");
+            let client = match args_to_gitlabapi_request_client(&args) {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            if args.health_check {
+                health_check(&client, &args);
+                std::process::exit(0);
+            }
+            verify_token(&client, &args);
+            Some(client)
+        }
+    };
+    if args.list_projects {
+        // --list-projects conflicts with --check, so there is a client
+        list_projects(client.as_ref().unwrap(), &args);
+        std::process::exit(0);
+    }
+
     // Translate args to file parser.
     // We dont need to check if the options are valid, because we already did that in verify_args
     // We make the parser mutable, because we might need to change the title and description column
@@ -300,7 +1925,7 @@ fn main() {
     let mut parser = args_to_parser(&args);
     // Attempt to read the file and extract the issues
     debug!("Parsing file...");
-    let fileissues = match parser.get_issues() {
+    let mut fileissues = match parser.get_issues() {
         Ok(issues) => issues,
         Err(e) => {
             error!("{}", e);
@@ -308,9 +1933,15 @@ fn main() {
         }
     };
     info!("Found {} issues in the file", fileissues.len());
-    fileissues
-        .iter()
-        .for_each(|issue| debug!("\t{}", issue.to_string()));
+    // Titles are compared with --prepend-title applied, as they will be created
+    if args.dedup || args.dedup_by.is_some() {
+        let by_title = args.dedup_by.as_deref() == Some("title");
+        let removed = issuefile::dedup_issues(&mut fileissues, by_title);
+        if removed > 0 {
+            warn!("Removed {} duplicate issues from the file", removed);
+        }
+    }
+    fileissues.iter().for_each(|issue| debug!("\t{}", issue));
 
     // Exit if user only wanted to check the file
     if args.check {
@@ -318,55 +1949,243 @@ fn main() {
         std::process::exit(0);
     }
 
-    // Create the gitlab api client
-    debug!("Creating GitLab API client...");
-    let client = match args_to_gitlabapi_request_client(&args) {
-        Ok(c) => c,
-        Err(e) => {
-            error!("{}", e);
+    // The client was only left out for --check
+    let client = client.unwrap();
+    // Resolve the group, if given, so the project can be looked up within it
+    let group = match (args.group_id, args.group_name.as_ref()) {
+        (Some(id), _) => Some(client.get_group(&id.to_string())),
+        (None, Some(path)) => Some(client.get_group(path)),
+        (None, None) => None,
+    };
+    let group = match group {
+        Some(Ok(g)) => {
+            debug!("Using group {}", g);
+            Some(g)
+        }
+        Some(Err(e)) => {
+            error!("Could not find group: {}", e);
             std::process::exit(1);
         }
+        None => None,
     };
-    // Check if our token is valid by trying to get the available projects
-    debug!("Getting projects from {}...", args.url.as_ref().unwrap());
-    let projects = match client.get_projects() {
-        Ok(p) => p,
-        Err(e) => {
-            error!("{}", e);
-            std::process::exit(1);
+    // Resolve the project of every issue that has one, once per project
+    let mut row_project_ids: HashMap<String, Option<u64>> = HashMap::new();
+    let mut listed_projects: Option<Vec<gitlabapi::GitLabProject>> = None;
+    // Paths of the projects we looked up, shown when asking for confirmation
+    let mut project_paths: HashMap<u64, String> = HashMap::new();
+    for project in fileissues.iter().filter_map(|issue| issue.project.as_ref()) {
+        if row_project_ids.contains_key(project) {
+            continue;
+        }
+        match resolve_issue_project(
+            &client,
+            group.as_ref(),
+            project,
+            &mut listed_projects,
+            &mut project_paths,
+        ) {
+            Ok(id) => {
+                row_project_ids.insert(project.clone(), id);
+            }
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    // Without a project for all issues, issues without a project of their own have nowhere to go
+    let has_default_project = args.project_name.is_some()
+        || args.project_id.is_some()
+        || (args.project_key.is_none() && args.project_index.is_none());
+    let mut unknown_projects: Vec<String> = row_project_ids
+        .iter()
+        .filter(|(_, id)| id.is_none())
+        .map(|(project, _)| format!("'{}'", project))
+        .collect();
+    unknown_projects.sort();
+    if !has_default_project && fileissues.iter().any(|issue| issue.project.is_none()) {
+        unknown_projects.push(String::from("(no project given)"));
+    }
+    if !unknown_projects.is_empty() {
+        match args.skip_unknown_project {
+            true => warn!(
+                "The projects {} do not exist, their issues are skipped",
+                unknown_projects.join(", ")
+            ),
+            false => {
+                error!(
+                    "The projects {} do not exist, use --skip-unknown-project to skip their issues",
+                    unknown_projects.join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    let first_row_project_id = fileissues
+        .iter()
+        .filter_map(|issue| issue.project.as_ref())
+        .find_map(|project| row_project_ids[project]);
+    let project_id = match args.project_name.as_ref() {
+        // Without a project for all issues, the project of the first issue is checked like one
+        _ if !has_default_project => match first_row_project_id {
+            Some(id) => id,
+            None => {
+                error!("None of the issues has a project to be created in");
+                std::process::exit(1);
+            }
+        },
+        // A namespaced path can be looked up directly, no need to list every project
+        Some(project_path) if project_path.contains('/') => {
+            debug!(
+                "Getting project {} from {}...",
+                project_path,
+                args.url.as_ref().unwrap()
+            );
+            match client.get_project_by_path(project_path) {
+                Ok(project) => {
+                    debug!("\t{}", project);
+                    project_paths.insert(project.id, project.path_with_namespace.clone());
+                    project.id
+                }
+                Err(e) => {
+                    error!("Could not find project with path '{}': {}", project_path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ if group.is_some() => {
+            // Only look for the project within the group
+            let group = group.as_ref().unwrap();
+            debug!("Getting projects of group {}...", group);
+            let projects = match client.get_projects_of_group(group.id) {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            info!(
+                "Found {} projects in group {}",
+                projects.len(),
+                group.full_path
+            );
+            projects.iter().for_each(|project| debug!("\t{}", project));
+            project_paths.extend(
+                projects
+                    .iter()
+                    .map(|p| (p.id, p.path_with_namespace.clone())),
+            );
+            if args.project_name.is_none() && args.project_id.is_none() {
+                // No project given, use the first project of the group
+                match projects.first() {
+                    Some(project) => {
+                        info!(
+                            "No project given, using the first project of the group: {}",
+                            project
+                        );
+                        project.id
+                    }
+                    None => {
+                        error!(
+                            "Group {} has no projects to create issues in",
+                            group.full_path
+                        );
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                match get_valid_project_id(&args, projects) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        _ => {
+            // Check if our token is valid by trying to get the available projects
+            debug!("Getting projects from {}...", args.url.as_ref().unwrap());
+            match client.get_projects() {
+                Ok(projects) => {
+                    info!(
+                        "Found {} projects that provided token has access to",
+                        projects.len()
+                    );
+                    projects.iter().for_each(|project| debug!("\t{}", project));
+                    project_paths.extend(
+                        projects
+                            .iter()
+                            .map(|p| (p.id, p.path_with_namespace.clone())),
+                    );
+                    // Verify that the project exists
+                    match get_valid_project_id(&args, projects) {
+                        Ok(id) => id,
+                        Err(e) => {
+                            error!("{}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                // Tokens with limited scopes, like job tokens, can not list projects
+                Err(gitlabapi::GitLabApiError::Http { status: 403, .. })
+                    if args.project_id.is_some() =>
+                {
+                    warn!("Token is not allowed to list projects, using the project id without verifying it");
+                    args.project_id.unwrap()
+                }
+                Err(e) => {
+                    if args.job_token {
+                        error!("Job tokens can not list projects, use --project-id or the full project path");
+                    }
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            }
         }
     };
     info!(
-        "Found {} projects that provided token has access to",
-        projects.len()
+        "Verified project id {} exists and matches the input",
+        project_id
     );
-    projects
+    // The project every issue is created in, None if its project does not exist
+    let issue_project_ids: Vec<Option<u64>> = fileissues
         .iter()
-        .for_each(|project| debug!("\t{}", project.to_string()));
-    // Verify that the project exists
-    let project_id = match get_valid_project_id(&args, projects) {
-        Ok(id) => id,
+        .map(|issue| match issue.project.as_ref() {
+            Some(project) => row_project_ids[project],
+            None if has_default_project => Some(project_id),
+            None => None,
+        })
+        .collect();
+    let mut target_project_ids: Vec<u64> = vec![project_id];
+    for id in issue_project_ids.iter().flatten() {
+        if !target_project_ids.contains(id) {
+            target_project_ids.push(*id);
+        }
+    }
+
+    // The members of the project are needed to find the ids of the assignees and the labels
+    // to verify --labels. Both are fetched once here and reused below.
+    let file_has_assignees = fileissues.iter().any(|issue| issue.assignee.is_some());
+    let needs_members = args.assignees.is_some() || file_has_assignees;
+    let needs_labels = args.labels.is_some();
+    debug!(
+        "Looking for members and labels of project {} ...",
+        project_id
+    );
+    let (project_members, project_labels) = match client.get_members_and_labels_of_project(
+        project_id,
+        needs_members,
+        args.include_inherited_members,
+        needs_labels,
+    ) {
+        Ok(m) => m,
         Err(e) => {
             error!("{}", e);
             std::process::exit(1);
         }
     };
-    info!(
-        "Verified project id {} exists and matches the input",
-        project_id
-    );
-
-    // If specified, verify that the assignee exists and is a member of the project
-    let mut assignee_id: Option<u64> = None;
-    if args.assignee.is_some() {
-        debug!("Looking for members of project {} ...", project_id);
-        let project_members = match client.get_members_of_project(project_id) {
-            Ok(m) => m,
-            Err(e) => {
-                error!("{}", e);
-                std::process::exit(1);
-            }
-        };
+    if needs_members {
         info!(
             "Found {} members of project {}",
             project_members.len(),
@@ -374,62 +2193,205 @@ fn main() {
         );
         project_members
             .iter()
-            .for_each(|member| debug!("\t{}", member.to_string()));
-
-        let our_assignee = args.assignee.as_ref().unwrap();
-        if args.verbose {
-            println!("Verifying that assignee {} exists...", our_assignee);
-        }
-        let mut assignee_exists = false;
-        for member in project_members {
-            if member.username == *our_assignee {
-                assignee_exists = true;
-                assignee_id = Some(member.id);
-                break;
-            }
-        }
-        match assignee_exists {
-            true => info!(
-                "Assignee {}:{} exists for project id {}",
-                assignee_id.unwrap(),
-                our_assignee,
-                project_id
+            .for_each(|member| debug!("\t{}", member));
+    }
+    if needs_labels {
+        info!(
+            "Found {} labels of project {}",
+            project_labels.len(),
+            project_id
+        );
+        project_labels
+            .iter()
+            .for_each(|label| debug!("\t{}", label));
+    }
+    // Assigned instead of the assignees that are not members of the project
+    let fallback_id = match args.assignee_fallback.as_ref() {
+        Some(fallback) if needs_members => {
+            match find_assignee(&project_members, fallback).unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            }) {
+                Some(member) => Some(member.id),
+                None => {
+                    error!(
+                        "The fallback assignee '{}' does not exist or is not a member of the project with id {}",
+                        fallback, project_id
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+    // If specified, verify that the assignees exist and are members of the project
+    let mut assignee_ids: Vec<u64> = args.assignee_id.into_iter().collect();
+    if let Some(our_assignees) = args.assignees.as_ref() {
+        for our_assignee in our_assignees.split(',').map(|a| a.trim()) {
+            info!("Verifying that assignee {} exists...", our_assignee);
+            match find_assignee(&project_members, our_assignee).unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            }) {
+                Some(member) => {
+                    info!(
+                        "Assignee {}:{} exists for project id {}",
+                        member.id, our_assignee, project_id
+                    );
+                    if !assignee_ids.contains(&member.id) {
+                        assignee_ids.push(member.id);
+                    }
+                }
+                None => match fallback_id {
+                    Some(id) => {
+                        warn!(
+                            "The assignee '{}' is not a member of the project with id {}, assigning '{}' instead",
+                            our_assignee,
+                            project_id,
+                            args.assignee_fallback.as_ref().unwrap()
+                        );
+                        if !assignee_ids.contains(&id) {
+                            assignee_ids.push(id);
+                        }
+                    }
+                    None if args.assignee_fallback_unset => warn!(
+                        "The assignee '{}' is not a member of the project with id {}, the issues are created without it",
+                        our_assignee, project_id
+                    ),
+                    None => {
+                        error!(
+                            "The assignee '{}' does not exist or is not a member of the project with id {}, use --assignee-fallback or --assignee-fallback-unset to create the issues anyway",
+                            our_assignee, project_id
+                        );
+                        std::process::exit(1);
+                    }
+                },
+            }
+        }
+    }
+    // Look up every assignee read from the file once
+    let mut file_assignee_ids: HashMap<String, u64> = HashMap::new();
+    let mut unknown_assignees: Vec<String> = Vec::new();
+    for our_assignee in fileissues
+        .iter()
+        .filter_map(|issue| issue.assignee.as_ref())
+    {
+        if file_assignee_ids.contains_key(our_assignee) || unknown_assignees.contains(our_assignee)
+        {
+            continue;
+        }
+        match find_assignee(&project_members, our_assignee).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1);
+        }) {
+            Some(member) => {
+                info!(
+                    "Assignee {}:{} exists for project id {}",
+                    member.id, our_assignee, project_id
+                );
+                file_assignee_ids.insert(our_assignee.clone(), member.id);
+            }
+            None => unknown_assignees.push(our_assignee.clone()),
+        }
+    }
+    if let (false, Some(id)) = (unknown_assignees.is_empty(), fallback_id) {
+        warn!(
+            "The assignees {:?} are not members of the project with id {}, assigning '{}' instead",
+            unknown_assignees,
+            project_id,
+            args.assignee_fallback.as_ref().unwrap()
+        );
+        for our_assignee in unknown_assignees.drain(..) {
+            file_assignee_ids.insert(our_assignee, id);
+        }
+    }
+    if !unknown_assignees.is_empty() {
+        match args.skip_unknown_assignee || args.assignee_fallback_unset {
+            true => warn!(
+                "The assignees {:?} are not members of the project with id {}, their issues are created without them",
+                unknown_assignees, project_id
             ),
             false => {
                 error!(
-                    "The assignee '{}' does not exist or is not a member of the project with id {}",
-                    our_assignee, project_id
+                    "The assignees {:?} do not exist or are not members of the project with id {}, use --skip-unknown-assignee to create their issues without them or --assignee-fallback to assign someone else",
+                    unknown_assignees, project_id
                 );
                 std::process::exit(1);
             }
         }
     }
 
-    // If specified, verify that the labels exist
-    if args.labels.is_some() {
-        debug!("Looking for labels of project {} ...", project_id);
-        let project_labels = match client.get_labels_of_project(project_id) {
-            Ok(l) => l,
+    // If specified, verify that the milestone exists in every project and get its ids.
+    // Missing milestones are only created after the confirmation
+    let mut milestone_ids: HashMap<u64, u64> = HashMap::new();
+    let mut missing_milestones: Vec<(u64, &String)> = Vec::new();
+    for (project_id, our_milestone) in target_project_ids
+        .iter()
+        .copied()
+        .filter_map(|id| args.milestone.as_ref().map(|m| (id, m)))
+    {
+        debug!("Looking for milestones of project {} ...", project_id);
+        let project_milestones = match client.get_milestones_of_project(project_id) {
+            Ok(m) => m,
             Err(e) => {
                 error!("{}", e);
                 std::process::exit(1);
             }
         };
         info!(
-            "Found {} labels of project {}",
-            project_labels.len(),
+            "Found {} milestones of project {}",
+            project_milestones.len(),
             project_id
         );
-        project_labels
+        project_milestones
             .iter()
-            .for_each(|label| debug!("\t{}", label.to_string()));
+            .for_each(|milestone| debug!("\t{}", milestone));
 
-        let our_labels = args
-            .labels
-            .as_ref()
-            .unwrap()
-            .split(',')
-            .collect::<Vec<&str>>();
+        match project_milestones
+            .iter()
+            .find(|milestone| milestone.title == *our_milestone)
+        {
+            Some(milestone) => {
+                info!(
+                    "Milestone {} exists for project id {}",
+                    milestone, project_id
+                );
+                milestone_ids.insert(project_id, milestone.id);
+            }
+            None if args.create_milestone && args.dry_run => print_dry_run(
+                &args,
+                &format!(
+                    "Dry run, would create milestone '{}' in the project with id {}",
+                    our_milestone, project_id
+                ),
+            ),
+            None if args.create_milestone => missing_milestones.push((project_id, our_milestone)),
+            None => {
+                error!(
+                    "The milestone '{}' does not exist in the project with id {}. Available milestones: {}",
+                    our_milestone,
+                    project_id,
+                    project_milestones
+                        .iter()
+                        .map(|milestone| format!("'{}'", milestone.title))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // If specified, verify that the labels exist. Missing labels are only created after
+    // the confirmation, with their color
+    let mut missing_labels: Vec<(&str, Option<String>)> = Vec::new();
+    if let Some(labels) = args.labels.as_ref() {
+        // Validated in verify_args
+        let label_colors = match args.label_color.as_ref() {
+            Some(l) => parse_label_colors(l).unwrap(),
+            None => HashMap::new(),
+        };
+        let our_labels = labels.split(',').collect::<Vec<&str>>();
         info!(
             "Verifying that labels '{:?}' exist in the project...",
             our_labels
@@ -442,9 +2404,16 @@ fn main() {
                     break;
                 }
             }
-            match label_exists {
-                true => (),
-                false => {
+            match (label_exists, args.create_labels) {
+                (true, _) => (),
+                (false, true) if args.dry_run => print_dry_run(
+                    &args,
+                    &format!("Dry run, would create label '{}'", our_label),
+                ),
+                (false, true) => {
+                    missing_labels.push((our_label, label_colors.get(our_label).cloned()))
+                }
+                (false, false) => {
                     error!(
                         "The label '{}' does not exist in the project with id {}",
                         our_label, project_id
@@ -453,20 +2422,453 @@ fn main() {
                 }
             }
         }
-        info!("All labels exist in the project");
+        if missing_labels.is_empty() {
+            info!("All labels exist in the project");
+        }
+    }
+    // If specified, get the titles of the issues already open in the projects
+    let mut existing_titles: HashSet<(u64, String)> = HashSet::new();
+    for project_id in target_project_ids
+        .iter()
+        .copied()
+        .filter(|_| args.skip_existing)
+    {
+        debug!("Looking for open issues of project {} ...", project_id);
+        let existing_issues = match client.get_issues_of_project(project_id) {
+            Ok(i) => i,
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        info!(
+            "Found {} open issues in project {}",
+            existing_issues.len(),
+            project_id
+        );
+        existing_issues
+            .iter()
+            .for_each(|issue| debug!("\t{}", issue));
+        existing_titles.extend(existing_issues.into_iter().map(|i| (project_id, i.title)));
+    }
+
+    // Give every issue the import id of earlier runs. The ids are saved before anything is
+    // created, so an issue whose request fails after all is found by the next run
+    let import_ids: Vec<Option<uuid::Uuid>> = match args.idempotent {
+        true => {
+            let mut state = match statefile::StateFile::from_path(&args.state_file) {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            // Tells apart issues with the same title
+            let mut occurrences: HashMap<(u64, &str), usize> = HashMap::new();
+            let ids = fileissues
+                .iter()
+                .zip(&issue_project_ids)
+                .map(|(issue, project_id)| {
+                    let project_id = (*project_id)?;
+                    let n = occurrences
+                        .entry((project_id, issue.title.as_str()))
+                        .or_insert(0);
+                    let import_id = state.import_id(project_id, &issue.title, *n);
+                    *n += 1;
+                    Some(import_id)
+                })
+                .collect();
+            if !args.dry_run {
+                if let Err(e) = state.save() {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            ids
+        }
+        false => vec![None; fileissues.len()],
+    };
+
+    // All checks passed, let the user have a last look before anything is created
+    if !args.yes && !args.dry_run && std::io::stdin().is_terminal() {
+        let count = issue_project_ids.iter().flatten().count();
+        let paths: Vec<String> = target_project_ids
+            .iter()
+            .filter(|id| issue_project_ids.contains(&Some(**id)))
+            .map(|id| match project_paths.get(id) {
+                Some(path) => path.clone(),
+                None => format!("with id {}", id),
+            })
+            .collect();
+        if !confirm_creation(count, &paths) {
+            eprintln!("Aborted, no issues were created");
+            std::process::exit(1);
+        }
+    }
+
+    // Create the missing milestones and labels only now, so declining the confirmation
+    // leaves the projects as they were
+    for (project_id, our_milestone) in missing_milestones {
+        match client.create_milestone(
+            project_id,
+            our_milestone,
+            args.milestone_due_date.as_deref(),
+        ) {
+            Ok(id) => {
+                info!(
+                    "Created milestone {}: {} in the project with id {}",
+                    id, our_milestone, project_id
+                );
+                milestone_ids.insert(project_id, id);
+            }
+            Err(e) => {
+                error!("Could not create milestone '{}': {}", our_milestone, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    for (our_label, color) in missing_labels {
+        match client.create_label(project_id, our_label, color.as_deref()) {
+            Ok(label) => info!(
+                "Created label {} in the project with id {}",
+                label, project_id
+            ),
+            Err(e) => {
+                error!("Could not create label '{}': {}", our_label, e);
+                std::process::exit(1);
+            }
+        }
     }
-    // All checks passed, now we can create the issues
+
+    // Now we can create the issues
     debug!("Creating issues...");
-    for fileissue in fileissues {
-        let issue =
-            gitlabapi::GitLabProjectIssue::new(project_id, &fileissue, &args.labels, assignee_id);
-        info!("Creating issue '{}'", issue.title);
+    // Local images in the descriptions are relative to the issues file, like description files
+    let images_dir = match args.file.as_ref().unwrap() {
+        f if f.as_os_str() == issuefile::STDIN_FILE => None,
+        f => f.parent().map(|p| p.to_path_buf()),
+    };
+    // Set to None if the instance turns out to not support epics
+    let mut epic_iid = args.epic_id;
+    let mut failed_issues: Vec<String> = Vec::new();
+    let mut created_count = 0;
+    let mut skipped_count = 0;
+    // Summary of every issue for --output json and --report
+    let mut results: Vec<report::IssueResult> = Vec::new();
+    // Project and iid of the created issues by title, and the links to add once all are created
+    let mut created_iids: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut pending_links = Vec::new();
+    // Only show a progress bar to someone watching, verbose logs would break it up
+    let progress = match std::io::stdout().is_terminal() && !args.verbose && !args.dry_run {
+        true => ProgressBar::new(fileissues.len() as u64).with_style(
+            ProgressStyle::with_template("{bar:40} {pos}/{len} issues, ETA {eta} {wide_msg}")
+                .unwrap(),
+        ),
+        false => ProgressBar::hidden(),
+    };
+    for ((fileissue, import_id), project_id) in fileissues
+        .into_iter()
+        .zip(import_ids)
+        .zip(issue_project_ids)
+    {
+        progress.set_message(fileissue.title.clone());
+        progress.inc(1);
+        let project_id = match project_id {
+            Some(id) => id,
+            None => {
+                info!(
+                    title = fileissue.title.as_str(), status = "skipped";
+                    "Skipping issue '{}', its project does not exist",
+                    fileissue.title
+                );
+                skipped_count += 1;
+                results.push(report::IssueResult::skipped(&fileissue.title, None));
+                continue;
+            }
+        };
+        if existing_titles.contains(&(project_id, fileissue.title.clone())) {
+            info!(
+                title = fileissue.title.as_str(), status = "skipped";
+                "Skipping issue '{}', it already exists",
+                fileissue.title
+            );
+            skipped_count += 1;
+            results.push(report::IssueResult::skipped(&fileissue.title, None));
+            continue;
+        }
+        // The assignee of the issue replaces the assignees for all issues
+        let issue_assignee_ids = match fileissue
+            .assignee
+            .as_ref()
+            .and_then(|a| file_assignee_ids.get(a))
+        {
+            Some(id) => vec![*id],
+            None => assignee_ids.clone(),
+        };
+        let mut issue = gitlabapi::GitLabProjectIssue::new(
+            project_id,
+            &fileissue,
+            &args.labels,
+            &issue_assignee_ids,
+            milestone_ids.get(&project_id).copied(),
+            args.confidential,
+            args.weight,
+            &args.issue_type,
+            &args.created_at,
+            &args.labels_mode,
+        );
+        if let Some(import_id) = import_id {
+            issue.set_import_id(import_id);
+            match client.find_issue_by_import_id(project_id, &import_id) {
+                Ok(Some(existing)) => {
+                    info!(
+                        title = issue.title.as_str(), status = "skipped", iid = existing.iid;
+                        "Skipping issue '{}', it was already imported as {}",
+                        issue.title, existing
+                    );
+                    skipped_count += 1;
+                    results.push(report::IssueResult::skipped(
+                        &issue.title,
+                        Some(existing.iid),
+                    ));
+                    continue;
+                }
+                Ok(None) => debug!("Issue '{}' was not imported before", issue.title),
+                Err(e) => {
+                    progress.suspend(|| {
+                        warn!(
+                            title = issue.title.as_str(), status = "failed";
+                            "Could not check if issue '{}' was already imported: {}",
+                            issue.title, e
+                        )
+                    });
+                    failed_issues.push(issue.title.clone());
+                    results.push(report::IssueResult::failed(&issue.title, e.to_string()));
+                    if args.fail_fast {
+                        break;
+                    }
+                    continue;
+                }
+            }
+        }
+        if args.dry_run {
+            match issue.create_issue_body() {
+                Ok(body) => print_dry_run(
+                    &args,
+                    &format!(
+                        "Dry run, would create issue: {}",
+                        serde_json::to_string(&body).unwrap()
+                    ),
+                ),
+                Err(e) => warn!("{}", e),
+            }
+            continue;
+        }
+        if args.upload_images && !args.validate_roundtrip {
+            if let Some(description) = issue.description() {
+                let description = progress.suspend(|| {
+                    upload_images(&client, project_id, description, images_dir.as_deref())
+                });
+                issue.set_description(description);
+            }
+        }
+        info!(title = issue.title.as_str(); "Creating issue '{}'", issue.title);
         debug!("Issue details: {:#?}", issue);
         match client.post_issue(&issue) {
-            Ok(_) => (),
+            Ok(posted) if args.validate_roundtrip => {
+                match client.delete_issue(project_id, posted.iid) {
+                    Ok(_) => {
+                        debug!(
+                            title = posted.title.as_str(), status = "validated";
+                            "Validated issue {}, it was deleted again",
+                            posted
+                        );
+                        created_count += 1;
+                        results.push(report::IssueResult::validated(&posted));
+                    }
+                    Err(e) => {
+                        progress.suspend(|| {
+                            error!(
+                                title = posted.title.as_str(), status = "failed";
+                                "Issue {} was created to validate it, but could not be deleted: {}",
+                                posted, e
+                            )
+                        });
+                        failed_issues.push(posted.title.clone());
+                        results.push(report::IssueResult::failed(
+                            &posted.title,
+                            format!(
+                                "created as {}, but could not be deleted: {}",
+                                posted.web_url, e
+                            ),
+                        ));
+                    }
+                }
+            }
+            Ok(posted) => {
+                info!(
+                    title = posted.title.as_str(), status = "created", iid = posted.iid,
+                    url = posted.web_url.as_str();
+                    "Created: {} -> {}",
+                    posted.title,
+                    posted.web_url
+                );
+                created_count += 1;
+                created_iids
+                    .entry(posted.title.clone())
+                    .or_insert((project_id, posted.iid));
+                if let (Some(epic), Some(group)) = (epic_iid, group.as_ref()) {
+                    match client.add_issue_to_epic(group.id, epic, posted.id) {
+                        Ok(_) => debug!("Added issue {} to epic {}", posted, epic),
+                        Err(gitlabapi::GitLabApiError::Http {
+                            status: 403 | 404, ..
+                        }) => {
+                            // Epics are not available, no point in trying for the other issues
+                            progress.suspend(|| {
+                                warn!(
+                                    "Could not add issues to epic {} of group {}, epics might not be supported by this GitLab instance or license",
+                                    epic, group.full_path
+                                )
+                            });
+                            epic_iid = None;
+                        }
+                        Err(e) => progress.suspend(|| {
+                            warn!(
+                                "Could not add issue '{}' to epic {}: {}",
+                                posted.title, epic, e
+                            )
+                        }),
+                    }
+                }
+                if let Some(parent_iid) = args.parent_iid {
+                    match client.link_issues(
+                        project_id,
+                        posted.iid,
+                        project_id,
+                        parent_iid,
+                        "relates_to",
+                    ) {
+                        Ok(_) => debug!("Linked issue {} to issue #{}", posted, parent_iid),
+                        Err(e) => progress.suspend(|| {
+                            warn!(
+                                "Could not link issue '{}' to issue #{}: {}",
+                                posted.title, parent_iid, e
+                            )
+                        }),
+                    }
+                }
+                if let Some(comment) = fileissue.comment.as_ref() {
+                    match client.add_note(project_id, posted.iid, comment) {
+                        Ok(_) => debug!("Added a comment to issue {}", posted),
+                        Err(e) => progress.suspend(|| {
+                            warn!(
+                                "Could not add the comment to issue '{}': {}",
+                                posted.title, e
+                            )
+                        }),
+                    }
+                }
+                results.push(report::IssueResult::created(&posted));
+                if !fileissue.links.is_empty() {
+                    pending_links.push((project_id, posted, fileissue.links));
+                }
+            }
             Err(e) => {
-                warn!("{}", e);
+                progress.suspend(|| {
+                    warn!(
+                        title = issue.title.as_str(), status = "failed";
+                        "Could not create issue '{}': {}",
+                        issue.title, e
+                    )
+                });
+                if let (gitlabapi::GitLabApiError::Http { status: 403, .. }, true) =
+                    (&e, issue.is_backdated())
+                {
+                    progress.suspend(|| {
+                        warn!("Setting the creation time of issues needs the owner role in the project or an admin token")
+                    });
+                }
+                failed_issues.push(issue.title.clone());
+                results.push(report::IssueResult::failed(&issue.title, e.to_string()));
+                if args.fail_fast {
+                    break;
+                }
+            }
+        }
+    }
+    progress.finish_and_clear();
+    // Link the issues now that all are created, they can point at issues further down the file
+    for (project_id, posted, links) in pending_links {
+        for (link_type, target) in links {
+            match created_iids.get(&target) {
+                Some((target_project_id, target_iid)) => match client.link_issues(
+                    project_id,
+                    posted.iid,
+                    *target_project_id,
+                    *target_iid,
+                    link_type,
+                ) {
+                    Ok(_) => debug!("Linked issue {} to '{}' ({})", posted, target, link_type),
+                    Err(e) => warn!(
+                        "Could not link issue '{}' to '{}' ({}): {}",
+                        posted.title, target, link_type, e
+                    ),
+                },
+                None => warn!(
+                    "Could not link issue '{}' to '{}', no issue with that title was created",
+                    posted.title, target
+                ),
+            }
+        }
+    }
+    if args.fail_fast && !failed_issues.is_empty() {
+        error!("Stopped at the first issue that could not be created (--fail-fast)");
+    }
+    if !failed_issues.is_empty() {
+        error!("Failed to create {} issues:", failed_issues.len());
+        failed_issues.iter().for_each(|title| error!("\t{}", title));
+    }
+    if args.dry_run {
+        return;
+    }
+    let mut summary = format!(
+        "{} {} issues, {} failed",
+        match args.validate_roundtrip {
+            true => "Validated",
+            false => "Created",
+        },
+        created_count,
+        failed_issues.len()
+    );
+    if args.skip_existing || args.idempotent {
+        summary.push_str(&format!(
+            ", skipped {} issues that already exist",
+            skipped_count
+        ));
+    }
+    if args.output == "json" {
+        // Keep stdout valid json
+        println!("{}", serde_json::to_string(&results).unwrap());
+        eprintln!("{}", summary);
+    } else {
+        let color = match args.color.as_str() {
+            "always" => true,
+            "never" => false,
+            // https://no-color.org
+            _ => {
+                std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
             }
+        };
+        print!("{}", report::summary_table(&results, color));
+    }
+    if let Some(path) = args.report.as_ref() {
+        if let Err(e) = report::write_report(path, &results) {
+            error!("{}", e);
+            std::process::exit(1);
         }
+        info!("Wrote report to {}", path.display());
+    }
+    if !failed_issues.is_empty() {
+        std::process::exit(EXIT_PARTIAL_FAILURE);
     }
 }
@@ -0,0 +1,161 @@
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::gitlabapi::PostedIssue;
+
+pub const SUPPORTED_REPORT_TYPES: [&str; 2] = ["csv", "json"];
+
+/// What happened to an issue, for --output json and --report
+#[derive(Serialize, Debug)]
+pub struct IssueResult {
+    pub title: String,
+    /// created, validated, skipped or failed
+    pub status: &'static str,
+    pub iid: Option<u64>,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+impl IssueResult {
+    pub fn created(posted: &PostedIssue) -> Self {
+        IssueResult {
+            title: posted.title.clone(),
+            status: "created",
+            iid: Some(posted.iid),
+            url: Some(posted.web_url.clone()),
+            error: None,
+        }
+    }
+    /// An issue that was created and deleted again by --validate-roundtrip
+    pub fn validated(posted: &PostedIssue) -> Self {
+        IssueResult {
+            title: posted.title.clone(),
+            status: "validated",
+            iid: None,
+            url: None,
+            error: None,
+        }
+    }
+    /// iid is the issue that already exists, if we know it
+    pub fn skipped(title: &str, iid: Option<u64>) -> Self {
+        IssueResult {
+            title: title.to_string(),
+            status: "skipped",
+            iid,
+            url: None,
+            error: None,
+        }
+    }
+    pub fn failed(title: &str, error: String) -> Self {
+        IssueResult {
+            title: title.to_string(),
+            status: "failed",
+            iid: None,
+            url: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Write the results to a csv or json file, chosen by the extension of the path
+pub fn write_report(path: &Path, results: &[IssueResult]) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let written = match extension.as_str() {
+        "csv" => write_csv(path, results),
+        "json" => match serde_json::to_string_pretty(results) {
+            Ok(json) => std::fs::write(path, json).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        _ => Err(format!(
+            "report type is not supported, expected one of {:?}",
+            SUPPORTED_REPORT_TYPES
+        )),
+    };
+    match written {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Could not write report {}: {}", path.display(), e)),
+    }
+}
+/// Table of the number of issues per status, followed by the title and error of every failed issue
+pub fn summary_table(results: &[IssueResult], color: bool) -> String {
+    let count = |status: &str| results.iter().filter(|r| r.status == status).count();
+    let mut rows = vec![("Created", count("created"))];
+    if count("validated") > 0 {
+        rows.push(("Validated", count("validated")));
+    }
+    rows.push(("Skipped", count("skipped")));
+    rows.push(("Failed", count("failed")));
+    let mut table = String::new();
+    for (label, n) in rows {
+        // Pad before coloring, the escape codes would count towards the width
+        let label = format!("{:<10}", label);
+        let label = match (color, n) {
+            (false, _) | (true, 0) => label,
+            (true, _) if label.starts_with("Failed") => label.red().bold().to_string(),
+            (true, _) if label.starts_with("Skipped") => label.yellow().to_string(),
+            (true, _) => label.green().to_string(),
+        };
+        table.push_str(&format!("{}{:>6}\n", label, n));
+    }
+    let failed: Vec<&IssueResult> = results.iter().filter(|r| r.status == "failed").collect();
+    if !failed.is_empty() {
+        table.push_str("\nFailed issues:\n");
+        for result in failed {
+            let title = match color {
+                true => result.title.bold().to_string(),
+                false => result.title.clone(),
+            };
+            table.push_str(&format!(
+                "  {}: {}\n",
+                title,
+                result.error.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+    table
+}
+fn write_csv(path: &Path, results: &[IssueResult]) -> Result<(), String> {
+    let mut writer = csv::Writer::from_path(path).map_err(|e| e.to_string())?;
+    for result in results {
+        writer.serialize(result).map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_report_has_the_error_of_failed_issues() {
+        let path = std::env::temp_dir().join("gitlab-issues-from-file-report-test.csv");
+        let results = [
+            IssueResult::skipped("task1", Some(4)),
+            IssueResult::failed("task2", String::from("title: is too long")),
+        ];
+        write_report(&path, &results).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            contents,
+            "title,status,iid,url,error\ntask1,skipped,4,,\ntask2,failed,,,title: is too long\n"
+        );
+    }
+
+    #[test]
+    fn summary_table_lists_the_failed_issues() {
+        let results = [
+            IssueResult::skipped("task1", None),
+            IssueResult::failed("task2", String::from("title: is too long")),
+        ];
+        assert_eq!(
+            summary_table(&results, false),
+            "Created        0\nSkipped        1\nFailed         1\n\nFailed issues:\n  task2: title: is too long\n"
+        );
+        assert!(summary_table(&results, true).contains("\u{1b}["));
+    }
+}
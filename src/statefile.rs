@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub const DEFAULT_STATE_FILE: &str = ".gitlab-issues-from-file-state.json";
+
+/// Import ids of the issues of earlier runs, kept between runs for --idempotent.
+///
+/// Every issue gets an import id, which is added to its description as a marker.
+/// A re-run reuses the import id of an issue, so an issue that was created even though
+/// the request failed, e.g. because of a timeout, can be found and is not created again.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct StateFile {
+    #[serde(skip)]
+    path: PathBuf,
+    /// "<project id>/<title>" -> import id
+    import_ids: BTreeMap<String, Uuid>,
+}
+impl StateFile {
+    /// Read the state file, a missing file is an empty state
+    pub fn from_path(path: &Path) -> Result<StateFile, String> {
+        let mut state = match std::fs::read_to_string(path) {
+            Ok(contents) => match serde_json::from_str::<StateFile>(&contents) {
+                Ok(s) => s,
+                Err(e) => return Err(format!("Could not parse state file: {}", e)),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => StateFile::default(),
+            Err(e) => return Err(format!("Could not read state file: {}", e)),
+        };
+        state.path = path.to_path_buf();
+        Ok(state)
+    }
+    /// Get the import id of an issue, a new one if the issue was not imported before.
+    /// occurrence tells apart issues with the same title, 0 for the first one
+    pub fn import_id(&mut self, project_id: u64, title: &str, occurrence: usize) -> Uuid {
+        let key = match occurrence {
+            0 => format!("{}/{}", project_id, title),
+            n => format!("{}/{} ({})", project_id, title, n + 1),
+        };
+        *self.import_ids.entry(key).or_insert_with(Uuid::new_v4)
+    }
+    pub fn save(&self) -> Result<(), String> {
+        let contents = match serde_json::to_string_pretty(self) {
+            Ok(c) => c,
+            Err(e) => return Err(format!("Could not serialize state: {}", e)),
+        };
+        match std::fs::write(&self.path, contents) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!(
+                "Could not write state file {}: {}",
+                self.path.display(),
+                e
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_ids_are_kept_per_issue() {
+        let mut state = StateFile::default();
+        let first = state.import_id(1, "task1", 0);
+        let duplicate = state.import_id(1, "task1", 1);
+        let other_project = state.import_id(2, "task1", 0);
+        assert_ne!(first, duplicate);
+        assert_ne!(first, other_project);
+        assert_eq!(state.import_id(1, "task1", 0), first);
+    }
+}